@@ -0,0 +1,38 @@
+use socket2::{Domain, Protocol, Socket, Type};
+use std::net::{IpAddr, SocketAddr};
+
+/// Strip the brackets from an IPv6 literal such as `[::1]`
+fn unbracket(host: &str) -> &str {
+    host.strip_prefix('[')
+        .and_then(|host| host.strip_suffix(']'))
+        .unwrap_or(host)
+}
+
+/// Resolve a configured host and port into a socket address
+/// Accepts IPv4 and IPv6 literals (with or without brackets) as well as host names
+pub async fn resolve(host: &str, port: u16) -> Option<SocketAddr> {
+    let host = unbracket(host.trim());
+    if let Ok(ip) = host.parse::<IpAddr>() {
+        return Some(SocketAddr::new(ip, port));
+    }
+    tokio::net::lookup_host((host, port)).await.ok()?.next()
+}
+
+/// Format a host and port for display, bracketing IPv6 literals
+pub fn display(host: &str, port: u16) -> String {
+    match unbracket(host.trim()).parse::<IpAddr>() {
+        Ok(ip) => SocketAddr::new(ip, port).to_string(),
+        Err(_) => format!("{}:{}", host, port),
+    }
+}
+
+/// Create an unbound, non-blocking socket for the given address
+/// IPv6 sockets also accept IPv4 traffic when `dual_stack` is set, and are IPv6 only otherwise
+pub fn socket(addr: SocketAddr, socket_type: Type, protocol: Protocol, dual_stack: bool) -> std::io::Result<Socket> {
+    let socket = Socket::new(Domain::for_address(addr), socket_type, Some(protocol))?;
+    if addr.is_ipv6() {
+        socket.set_only_v6(!dual_stack)?;
+    }
+    socket.set_nonblocking(true)?;
+    Ok(socket)
+}
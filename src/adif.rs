@@ -0,0 +1,294 @@
+use crate::charset;
+
+use encoding_rs::Encoding;
+
+/// A single ADIF field, `<NAME:LEN:TYPE>value`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Field {
+    /// Field name in upper case
+    pub name: String,
+    /// Optional data type indicator, e.g. `D` or `N`
+    pub data_type: Option<String>,
+    pub value: String,
+}
+
+/// Split ADIF into records of fields
+/// Field values are read by their declared byte length, so they may contain `<`,
+/// line breaks and leading or trailing spaces. Text before `<EOH>` is skipped
+/// as header. A malformed record is returned as an error and does not affect
+/// the records around it. Values that are not valid UTF-8 are decoded in `charset`.
+pub fn parse(input: &[u8], charset: &'static Encoding) -> Vec<Result<Vec<Field>, AdifError>> {
+    let mut records = Vec::new();
+    let mut fields = Vec::new();
+    let mut error = None;
+    let mut record_start = None;
+    let mut pos = data_start(input);
+
+    while let Some(start) = find(input, pos, b'<') {
+        record_start.get_or_insert(start);
+
+        // The tag ends at the next `>`, unless another tag starts first
+        let end = match input[start + 1..].iter().position(|&b| b == b'>' || b == b'<') {
+            Some(len) if input[start + 1 + len] == b'>' => start + 1 + len,
+            Some(len) => {
+                error.get_or_insert(AdifError::InvalidTag(start));
+                pos = start + 1 + len;
+                continue;
+            }
+            None => {
+                error.get_or_insert(AdifError::InvalidTag(start));
+                break;
+            }
+        };
+
+        let tag = String::from_utf8_lossy(&input[start + 1..end]);
+        let mut parts = tag.split(':').map(str::trim);
+        let name = parts.next().unwrap_or_default().to_uppercase();
+        pos = end + 1;
+
+        match name.as_str() {
+            "" => {
+                error.get_or_insert(AdifError::InvalidTag(start));
+                continue;
+            }
+            "EOR" => {
+                match error.take() {
+                    Some(e) => records.push(Err(e)),
+                    None if !fields.is_empty() => records.push(Ok(std::mem::take(&mut fields))),
+                    None => {}
+                }
+                fields.clear();
+                record_start = None;
+                continue;
+            }
+            // Everything so far has been header fields
+            "EOH" => {
+                fields.clear();
+                error = None;
+                record_start = None;
+                continue;
+            }
+            _ => {}
+        }
+
+        let length = match parts.next().map(str::parse::<usize>) {
+            Some(Ok(length)) => length,
+            Some(Err(_)) => {
+                error.get_or_insert(AdifError::InvalidLength(name, start));
+                continue;
+            }
+            None => {
+                error.get_or_insert(AdifError::MissingLength(name, start));
+                continue;
+            }
+        };
+        let data_type = parts.next().filter(|data_type| !data_type.is_empty()).map(str::to_uppercase);
+
+        let Some(length) = value_length(input, pos, length) else {
+            error.get_or_insert(AdifError::Truncated(name, start));
+            break;
+        };
+        let value = charset::decode(&input[pos..pos + length], charset).into_owned();
+        fields.push(Field { name, data_type, value });
+        pos += length;
+    }
+
+    if let Some(start) = record_start
+        && (error.is_some() || !fields.is_empty()) {
+        records.push(Err(error.unwrap_or(AdifError::MissingEor(start))));
+    }
+    records
+}
+
+/// Offset of the first record
+/// Input that does not start with a tag begins with a free-text header ending at `<EOH>`
+fn data_start(input: &[u8]) -> usize {
+    let first = input.iter().position(|b| !b.is_ascii_whitespace());
+    if first.is_none_or(|first| input[first] == b'<') {
+        return 0;
+    }
+    input.windows(5)
+        .position(|window| window.eq_ignore_ascii_case(b"<eoh>"))
+        .map(|start| start + 5)
+        .unwrap_or(0)
+}
+
+/// Number of bytes taken by a value declared with `length` at `pos`
+/// ADIF lengths count bytes, but some loggers count UTF-8 characters instead.
+/// Characters are counted when the byte count would cut a character in half
+/// or leave text before the next tag, and counting characters does not.
+pub fn value_length(input: &[u8], pos: usize, length: usize) -> Option<usize> {
+    let end = pos.checked_add(length)?;
+    let bytes = input.get(pos..end)?;
+    let clean = std::str::from_utf8(bytes).is_ok() && ends_at_tag(input, end);
+    match utf8_length(&input[pos..], length) {
+        Some(chars) if chars != length && !clean && ends_at_tag(input, pos + chars) => Some(chars),
+        _ => Some(length),
+    }
+}
+
+/// Byte length of the first `count` characters, if they are valid UTF-8
+fn utf8_length(data: &[u8], count: usize) -> Option<usize> {
+    let mut len = 0;
+    for _ in 0..count {
+        let width = match data.get(len)? {
+            0x00..=0x7F => 1,
+            0xC2..=0xDF => 2,
+            0xE0..=0xEF => 3,
+            0xF0..=0xF4 => 4,
+            _ => return None,
+        };
+        std::str::from_utf8(data.get(len..len + width)?).ok()?;
+        len += width;
+    }
+    Some(len)
+}
+
+/// Whether only whitespace lies between `pos` and the next tag or the end of input
+fn ends_at_tag(input: &[u8], pos: usize) -> bool {
+    input[pos..].iter()
+        .find(|b| !b.is_ascii_whitespace())
+        .is_none_or(|&b| b == b'<')
+}
+
+/// Find a byte at or after `pos`
+fn find(input: &[u8], pos: usize, byte: u8) -> Option<usize> {
+    input.get(pos..)?.iter().position(|&b| b == byte).map(|offset| pos + offset)
+}
+
+/// ADI and ADX syntax errors, with the byte offset of the offending tag or record
+#[derive(Debug, Clone, PartialEq)]
+pub enum AdifError {
+    InvalidTag(usize),
+    MissingLength(String, usize),
+    InvalidLength(String, usize),
+    Truncated(String, usize),
+    MissingEor(usize),
+    UnclosedRecord(usize),
+    InvalidElement(usize),
+}
+
+impl std::fmt::Display for AdifError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AdifError::InvalidTag(offset) => {
+                write!(f, "Malformed ADIF tag at byte {}", offset)
+            }
+            AdifError::MissingLength(name, offset) => {
+                write!(f, "ADIF field {} at byte {} has no length", name, offset)
+            }
+            AdifError::InvalidLength(name, offset) => {
+                write!(f, "ADIF field {} at byte {} has an invalid length", name, offset)
+            }
+            AdifError::Truncated(name, offset) => {
+                write!(f, "ADIF field {} at byte {} is shorter than its length", name, offset)
+            }
+            AdifError::MissingEor(offset) => {
+                write!(f, "ADIF record at byte {} has no <EOR>", offset)
+            }
+            AdifError::UnclosedRecord(offset) => {
+                write!(f, "ADX record at byte {} has no </RECORD>", offset)
+            }
+            AdifError::InvalidElement(offset) => {
+                write!(f, "Malformed ADX element at byte {}", offset)
+            }
+        }
+    }
+}
+
+impl std::error::Error for AdifError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_utf8(input: &str) -> Vec<Result<Vec<Field>, AdifError>> {
+        parse(input.as_bytes(), encoding_rs::WINDOWS_1252)
+    }
+
+    fn values(input: &str) -> Vec<(String, String)> {
+        let mut records = parse_utf8(input);
+        assert_eq!(records.len(), 1, "{:?}", records);
+        records.remove(0).unwrap().into_iter().map(|field| (field.name, field.value)).collect()
+    }
+
+    fn field(name: &str, value: &str) -> (String, String) {
+        (name.to_string(), value.to_string())
+    }
+
+    #[test]
+    fn values_may_contain_tags() {
+        assert_eq!(
+            values("<COMMENT:11>a<b>c<eor>d <CALL:4>DL1A<eor>"),
+            vec![field("COMMENT", "a<b>c<eor>d"), field("CALL", "DL1A")],
+        );
+    }
+
+    #[test]
+    fn values_may_span_lines() {
+        assert_eq!(
+            values("<NOTES:12>line 1\r\nline<call:4>DL1A\n<EOR>"),
+            vec![field("NOTES", "line 1\r\nline"), field("CALL", "DL1A")],
+        );
+    }
+
+    #[test]
+    fn field_names_may_contain_digits() {
+        assert_eq!(
+            values("<USERDEF1:3>EPC<APP_N1MM_EXCHANGE1:3:S>599<EOR>"),
+            vec![field("USERDEF1", "EPC"), field("APP_N1MM_EXCHANGE1", "599")],
+        );
+        assert_eq!(parse_utf8("<FREQ:6:N>14.074<EOR>")[0].as_ref().unwrap()[0].data_type.as_deref(), Some("N"));
+    }
+
+    #[test]
+    fn header_is_skipped() {
+        assert_eq!(
+            values("Exported by <someone>\r\n<ADIF_VER:5>3.1.4 <PROGRAMID:4>test\r\n<EOH>\r\n<CALL:4>DL1A<EOR>"),
+            vec![field("CALL", "DL1A")],
+        );
+        assert_eq!(values("<ADIF_VER:5>3.1.4<EOH><CALL:4>DL1A<EOR>"), vec![field("CALL", "DL1A")]);
+    }
+
+    #[test]
+    fn malformed_records_do_not_affect_others() {
+        let records = parse_utf8("<CALL:4>DL1A<EOR><CALL>DL2B<EOR><CALL:x>DL3C<EOR><CALL:4>DL4D<EOR>");
+        assert_eq!(records.len(), 4);
+        assert!(matches!(&records[1], Err(AdifError::MissingLength(name, 17)) if name == "CALL"));
+        assert!(matches!(&records[2], Err(AdifError::InvalidLength(name, 32)) if name == "CALL"));
+        assert_eq!(records[3].as_ref().unwrap()[0].value, "DL4D");
+
+        assert!(matches!(parse_utf8("<CALL:4 DL1A<EOR>")[0], Err(AdifError::InvalidTag(0))));
+        assert!(matches!(parse_utf8("<CALL:4>DL1A")[0], Err(AdifError::MissingEor(0))));
+        assert!(matches!(&parse_utf8("<CALL:10>DL1A<EOR>")[0], Err(AdifError::Truncated(name, 0)) if name == "CALL"));
+    }
+
+    #[test]
+    fn huge_lengths_are_truncated() {
+        let records = parse_utf8("<CALL:18446744073709551615>x<EOR>");
+        assert!(matches!(&records[0], Err(AdifError::Truncated(name, 0)) if name == "CALL"));
+        let records = parse_utf8("<CALL:99999999999999999999999>x<EOR>");
+        assert!(matches!(&records[0], Err(AdifError::InvalidLength(name, 0)) if name == "CALL"));
+    }
+
+    #[test]
+    fn lengths_count_bytes() {
+        assert_eq!(values("<NAME:7>Jürgen<CALL:4>DL1A<EOR>"), vec![field("NAME", "Jürgen"), field("CALL", "DL1A")]);
+        assert_eq!(values("<QTH:6>東京<EOR>"), vec![field("QTH", "東京")]);
+    }
+
+    #[test]
+    fn lengths_counting_characters_are_accepted() {
+        // The byte count would cut `ü` in half
+        assert_eq!(values("<NAME:6>Jürgen<CALL:4>DL1A<EOR>"), vec![field("NAME", "Jürgen"), field("CALL", "DL1A")]);
+        // The byte count would leave `f` before the next tag
+        assert_eq!(values("<NAME:5>Józef <CALL:4>DL1A<EOR>"), vec![field("NAME", "Józef"), field("CALL", "DL1A")]);
+        assert_eq!(values("<QTH:2>東京<EOR>"), vec![field("QTH", "東京")]);
+    }
+
+    #[test]
+    fn other_charsets_are_decoded() {
+        let records = parse(b"<NAME:6>J\xFCrgen<EOR>", encoding_rs::WINDOWS_1252);
+        assert_eq!(records[0].as_ref().unwrap()[0].value, "Jürgen");
+    }
+}
@@ -0,0 +1,186 @@
+use crate::adif;
+
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::time::{Duration, Instant};
+
+/// Maximum amount of incomplete ADIF kept per source before it is discarded
+const MAX_PENDING: usize = 1024 * 1024;
+
+/// Maximum number of senders whose incomplete ADIF is kept at the same time
+const MAX_SOURCES: usize = 64;
+
+/// Time after which incomplete ADIF of a silent sender is discarded
+const IDLE_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// End-of-record tag names of ADI and ADX, compared case-insensitively
+const RECORD_ENDS: &[&[u8]] = &[b"eor", b"/record"];
+
+/// Reassembles ADIF that arrives split over several packets or stream reads
+/// Records are only released once their `<EOR>` (or ADX `</RECORD>`) has arrived
+#[derive(Debug, Default)]
+pub struct AdifBuffer {
+    pending: Vec<u8>,
+}
+
+impl AdifBuffer {
+    /// Append received data and return everything up to and including the last record end
+    /// A header (ending with `<EOH>`) received earlier stays in front of the returned records
+    /// Incomplete ADIF beyond `MAX_PENDING` bytes is discarded and reported as an error
+    pub fn push(&mut self, data: &[u8]) -> Result<Option<Vec<u8>>, AdifBufferError> {
+        self.pending.extend_from_slice(data);
+
+        match rfind_record_end(&self.pending) {
+            Some(end) => Ok(Some(self.pending.drain(..end).collect())),
+            None if self.pending.len() > MAX_PENDING => {
+                let discarded = self.pending.len();
+                self.pending.clear();
+                Err(AdifBufferError::Overflow(discarded))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Whether no incomplete record is waiting for more data
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+}
+
+/// Stream buffers of many senders, e.g. one per UDP source address
+/// Only senders with incomplete ADIF are kept; idle ones are discarded and their
+/// number is capped, as UDP source addresses are easily spoofed
+#[derive(Debug)]
+pub struct AdifBuffers<K> {
+    buffers: HashMap<K, (AdifBuffer, Instant)>,
+}
+
+impl<K> Default for AdifBuffers<K> {
+    fn default() -> Self {
+        Self { buffers: HashMap::new() }
+    }
+}
+
+impl<K: Clone + Eq + Hash> AdifBuffers<K> {
+    /// Append data received from a sender and return its complete records
+    pub fn push(&mut self, key: K, data: &[u8]) -> Result<Option<Vec<u8>>, AdifBufferError> {
+        let now = Instant::now();
+        self.buffers.retain(|_, (_, updated)| now.duration_since(*updated) < IDLE_TIMEOUT);
+        if !self.buffers.contains_key(&key) && self.buffers.len() >= MAX_SOURCES {
+            let oldest = self.buffers.iter()
+                .min_by_key(|(_, (_, updated))| *updated)
+                .map(|(key, _)| key.clone());
+            if let Some(oldest) = oldest {
+                self.buffers.remove(&oldest);
+            }
+        }
+
+        let (buffer, updated) = self.buffers.entry(key.clone()).or_insert_with(|| (AdifBuffer::default(), now));
+        *updated = now;
+        let records = buffer.push(data);
+        if buffer.is_empty() {
+            self.buffers.remove(&key);
+        }
+        records
+    }
+}
+
+/// ADIF buffer error types
+#[derive(Debug)]
+pub enum AdifBufferError {
+    /// Incomplete ADIF of this many bytes was discarded without a record end
+    Overflow(usize),
+}
+
+impl std::fmt::Display for AdifBufferError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AdifBufferError::Overflow(len) => write!(f, "Discarded {} bytes of ADIF without <EOR>", len),
+        }
+    }
+}
+
+impl std::error::Error for AdifBufferError {}
+
+/// Find the end (exclusive) of the last `<EOR>` or `</RECORD>` tag, ignoring case
+/// Field values are skipped by their declared length, so record end tags inside
+/// values are not mistaken for the end of a record
+pub fn rfind_record_end(data: &[u8]) -> Option<usize> {
+    let mut last = None;
+    let mut pos = 0;
+
+    while let Some(start) = data[pos..].iter().position(|&b| b == b'<').map(|offset| pos + offset) {
+        // The tag ends at the next `>`, unless another tag starts first
+        let end = match data[start + 1..].iter().position(|&b| b == b'>' || b == b'<') {
+            Some(len) if data[start + 1 + len] == b'>' => start + 1 + len,
+            Some(len) => {
+                pos = start + 1 + len;
+                continue;
+            }
+            None => break,
+        };
+        let tag = &data[start + 1..end];
+        pos = end + 1;
+
+        if RECORD_ENDS.iter().any(|record_end| tag.trim_ascii().eq_ignore_ascii_case(record_end)) {
+            last = Some(pos);
+            continue;
+        }
+        let length = tag.split(|&b| b == b':')
+            .nth(1)
+            .and_then(|length| std::str::from_utf8(length).ok())
+            .and_then(|length| length.trim().parse::<usize>().ok());
+        if let Some(length) = length {
+            match adif::value_length(data, pos, length) {
+                Some(length) => pos += length,
+                // The rest of the value has not arrived yet
+                None => break,
+            }
+        }
+    }
+    last
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn record_ends_inside_values_are_skipped() {
+        assert_eq!(rfind_record_end(b"<COMMENT:11>foo<eor>bar"), None);
+        assert_eq!(rfind_record_end(b"<COMMENT:11>foo<eor>bar<EOR>x"), Some(28));
+        assert_eq!(rfind_record_end(b"<CALL:4>DL1A<EOR><CALL:4>DL"), Some(17));
+        assert_eq!(rfind_record_end(b"<RECORD><CALL>DL1A</CALL></RECORD></RECORDS>"), Some(34));
+    }
+
+    #[test]
+    fn records_split_inside_a_value_are_reassembled() {
+        let mut buffer = AdifBuffer::default();
+        assert_eq!(buffer.push(b"<CALL:4>DL1A<COMMENT:11>foo<eor>").unwrap(), None);
+        assert_eq!(buffer.push(b"bar<EOR>").unwrap(), Some(b"<CALL:4>DL1A<COMMENT:11>foo<eor>bar<EOR>".to_vec()));
+        assert!(buffer.is_empty());
+    }
+
+    #[test]
+    fn oversized_incomplete_adif_is_reported() {
+        let mut buffer = AdifBuffer::default();
+        assert_eq!(buffer.push(&vec![b'x'; MAX_PENDING]).unwrap(), None);
+        assert!(matches!(buffer.push(b"<CALL:4>DL"), Err(AdifBufferError::Overflow(len)) if len == MAX_PENDING + 10));
+        assert!(buffer.is_empty());
+        assert_eq!(buffer.push(b"<CALL:4>DL1A<EOR>").unwrap(), Some(b"<CALL:4>DL1A<EOR>".to_vec()));
+    }
+
+    #[test]
+    fn only_incomplete_senders_are_kept() {
+        let mut buffers = AdifBuffers::default();
+        assert!(buffers.push(1, b"<CALL:4>DL1A<EOR>").unwrap().is_some());
+        assert!(buffers.buffers.is_empty());
+
+        for sender in 0..MAX_SOURCES * 2 {
+            assert_eq!(buffers.push(sender, b"<CALL:4>DL").unwrap(), None);
+        }
+        assert_eq!(buffers.buffers.len(), MAX_SOURCES);
+        assert!(buffers.buffers.contains_key(&(MAX_SOURCES * 2 - 1)));
+        assert_eq!(buffers.push(MAX_SOURCES * 2 - 1, b"1A<EOR>").unwrap(), Some(b"<CALL:4>DL1A<EOR>".to_vec()));
+    }
+}
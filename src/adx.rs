@@ -0,0 +1,287 @@
+use crate::adif::{AdifError, Field};
+use crate::charset;
+
+use encoding_rs::Encoding;
+use regex::Regex;
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+/// ADIF version written to ADX headers
+const ADIF_VERSION: &str = "3.1.4";
+
+/// QSO fields defined by ADIF; other fields are written as `USERDEF`
+const ADIF_FIELDS: &[&str] = &[
+    "ADDRESS", "ADDRESS_INTL", "AGE", "ALTITUDE", "ANT_AZ", "ANT_EL", "ANT_PATH", "ARRL_SECT",
+    "AWARD_GRANTED", "AWARD_SUBMITTED", "A_INDEX", "BAND", "BAND_RX", "CALL", "CHECK", "CLASS",
+    "CLUBLOG_QSO_UPLOAD_DATE", "CLUBLOG_QSO_UPLOAD_STATUS", "CNTY", "COMMENT", "COMMENT_INTL",
+    "CONT", "CONTACTED_OP", "CONTEST_ID", "COUNTRY", "COUNTRY_INTL", "CQZ", "CREDIT_GRANTED",
+    "CREDIT_SUBMITTED", "DARC_DOK", "DISTANCE", "DXCC", "EMAIL", "EQ_CALL", "EQSL_QSLRDATE",
+    "EQSL_QSLSDATE", "EQSL_QSL_RCVD", "EQSL_QSL_SENT", "FISTS", "FISTS_CC", "FORCE_INIT", "FREQ",
+    "FREQ_RX", "GRIDSQUARE", "GRIDSQUARE_EXT", "GUEST_OP", "HAMLOGEU_QSO_UPLOAD_DATE",
+    "HAMLOGEU_QSO_UPLOAD_STATUS", "HAMQTH_QSO_UPLOAD_DATE", "HAMQTH_QSO_UPLOAD_STATUS",
+    "HRDLOG_QSO_UPLOAD_DATE", "HRDLOG_QSO_UPLOAD_STATUS", "IOTA", "IOTA_ISLAND_ID", "ITUZ",
+    "K_INDEX", "LAT", "LON", "LOTW_QSLRDATE", "LOTW_QSLSDATE", "LOTW_QSL_RCVD", "LOTW_QSL_SENT",
+    "MAX_BURSTS", "MODE", "MS_SHOWER", "MY_ALTITUDE", "MY_ANTENNA", "MY_ANTENNA_INTL",
+    "MY_ARRL_SECT", "MY_CITY", "MY_CITY_INTL", "MY_CNTY", "MY_COUNTRY", "MY_COUNTRY_INTL",
+    "MY_CQ_ZONE", "MY_DXCC", "MY_FISTS", "MY_GRIDSQUARE", "MY_GRIDSQUARE_EXT", "MY_IOTA",
+    "MY_IOTA_ISLAND_ID", "MY_ITU_ZONE", "MY_LAT", "MY_LON", "MY_NAME", "MY_NAME_INTL",
+    "MY_POSTAL_CODE", "MY_POSTAL_CODE_INTL", "MY_POTA_REF", "MY_RIG", "MY_RIG_INTL", "MY_SIG",
+    "MY_SIG_INFO", "MY_SIG_INFO_INTL", "MY_SIG_INTL", "MY_SOTA_REF", "MY_STATE", "MY_STREET",
+    "MY_STREET_INTL", "MY_USACA_COUNTIES", "MY_VUCC_GRIDS", "MY_WWFF_REF", "NAME", "NAME_INTL",
+    "NOTES", "NOTES_INTL", "NR_BURSTS", "NR_PINGS", "OPERATOR", "OWNER_CALLSIGN", "PFX",
+    "POTA_REF", "PRECEDENCE", "PROP_MODE", "PUBLIC_KEY", "QRZCOM_QSO_DOWNLOAD_DATE",
+    "QRZCOM_QSO_DOWNLOAD_STATUS", "QRZCOM_QSO_UPLOAD_DATE", "QRZCOM_QSO_UPLOAD_STATUS", "QSLMSG",
+    "QSLMSG_INTL", "QSLMSG_RCVD", "QSLRDATE", "QSLSDATE", "QSL_RCVD", "QSL_RCVD_VIA", "QSL_SENT",
+    "QSL_SENT_VIA", "QSL_VIA", "QSO_COMPLETE", "QSO_DATE", "QSO_DATE_OFF", "QSO_RANDOM", "QTH",
+    "QTH_INTL", "REGION", "RIG", "RIG_INTL", "RST_RCVD", "RST_SENT", "RX_PWR", "SAT_MODE",
+    "SAT_NAME", "SFI", "SIG", "SIG_INFO", "SIG_INFO_INTL", "SIG_INTL", "SILENT_KEY", "SKCC",
+    "SOTA_REF", "SRX", "SRX_STRING", "STATE", "STATION_CALLSIGN", "STX", "STX_STRING", "SUBMODE",
+    "SWL", "TEN_TEN", "TIME_OFF", "TIME_ON", "TX_PWR", "UKSMG", "USACA_COUNTIES", "VE_PROV",
+    "VUCC_GRIDS", "WEB", "WWFF_REF",
+];
+
+/// Check whether data is ADX (XML ADIF) rather than ADI
+/// Besides complete documents, this also recognises `<RECORD>` elements
+/// received on their own, as released by the stream buffers. Closing tags in
+/// front, such as the `</RECORDS></ADX>` a buffer keeps after the last record
+/// of the previous document, are skipped.
+pub fn is_adx(data: &[u8]) -> bool {
+    let mut data = data.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(data).trim_ascii_start();
+    while data.starts_with(b"</")
+        && let Some(end) = data.iter().position(|&b| b == b'>') {
+        data = data[end + 1..].trim_ascii_start();
+    }
+    let start = data.get(..7).unwrap_or(data).to_ascii_uppercase();
+    start.starts_with(b"<?XML") || start.starts_with(b"<ADX") || start.starts_with(b"<RECORD")
+}
+
+/// Decode an ADX document in the encoding of its XML declaration
+/// Without a declaration it is UTF-8, or `fallback` if it is not valid UTF-8
+pub fn decode<'a>(data: &'a [u8], fallback: &'static Encoding) -> Cow<'a, str> {
+    let declaration = Regex::new(r#"^\x{FEFF}?\s*<\?xml[^>]*\sencoding\s*=\s*["']([^"']+)["']"#).unwrap();
+    let head = String::from_utf8_lossy(&data[..data.len().min(200)]);
+    let declared = declaration.captures(&head)
+        .and_then(|cap| Encoding::for_label(cap[1].as_bytes()))
+        .filter(|encoding| *encoding != encoding_rs::UTF_8);
+    match declared {
+        Some(encoding) => encoding.decode_without_bom_handling(data).0,
+        None => charset::decode(data, fallback),
+    }
+}
+
+/// Split ADX into records of fields
+/// `<APP PROGRAMID="X" FIELDNAME="Y">` becomes `APP_X_Y` and `<USERDEF FIELDNAME="Y">`
+/// becomes `Y`, matching their ADI names, with the data type declared in the
+/// header. Records are read wherever they
+/// appear, so a document cut into pieces by a stream buffer is still parsed.
+pub fn parse(input: &str) -> Vec<Result<Vec<Field>, AdifError>> {
+    let element = Regex::new(
+        r#"<([A-Za-z][A-Za-z0-9_]*)((?:\s+[A-Za-z_]+\s*=\s*"[^"]*")*)\s*(?:/>|>([^<]*)</([A-Za-z][A-Za-z0-9_]*)\s*>)"#,
+    ).unwrap();
+    let attribute = Regex::new(r#"([A-Za-z_]+)\s*=\s*"([^"]*)""#).unwrap();
+    let userdef = Regex::new(r#"(?i)<USERDEF\b([^>]*)>([^<]*)</USERDEF\s*>"#).unwrap();
+
+    // Offsets stay valid because ASCII upper-casing keeps the length
+    let upper = input.to_ascii_uppercase();
+
+    // Data types of user-defined fields, declared in the header
+    let header = upper.find("</HEADER>").map_or("", |end| &input[..end]);
+    let userdef_types: HashMap<String, String> = userdef.captures_iter(header)
+        .filter_map(|cap| {
+            let data_type = attribute.captures_iter(&cap[1])
+                .find(|attr| attr[1].eq_ignore_ascii_case("TYPE"))
+                .map(|attr| attr[2].to_uppercase())?;
+            Some((unescape(cap[2].trim()).to_uppercase(), data_type))
+        })
+        .collect();
+
+    let mut records = Vec::new();
+    let mut pos = 0;
+
+    while let Some(start) = upper[pos..].find("<RECORD>").map(|offset| pos + offset) {
+        let content_start = start + "<RECORD>".len();
+        let Some(end) = upper[content_start..].find("</RECORD>").map(|offset| content_start + offset) else {
+            records.push(Err(AdifError::UnclosedRecord(start)));
+            break;
+        };
+        pos = end + "</RECORD>".len();
+
+        let content = &input[content_start..end];
+        let mut fields = Vec::new();
+        let mut last = 0;
+        let mut error = None;
+        for cap in element.captures_iter(content) {
+            let matched = cap.get(0).unwrap();
+            let tag = &cap[1];
+            // Text between elements and mismatched closing tags are not valid ADX
+            if !content[last..matched.start()].trim().is_empty()
+                || cap.get(4).is_some_and(|close| !close.as_str().eq_ignore_ascii_case(tag)) {
+                error = Some(AdifError::InvalidElement(content_start + last));
+                break;
+            }
+            last = matched.end();
+
+            let value = cap.get(3).map(|value| unescape(value.as_str())).unwrap_or_default();
+            if value.is_empty() {
+                continue;
+            }
+            let attributes: Vec<(String, String)> = attribute.captures_iter(&cap[2])
+                .map(|attr| (attr[1].to_uppercase(), unescape(&attr[2])))
+                .collect();
+            let attr = |name: &str| attributes.iter()
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| value.clone());
+
+            let name = match tag.to_uppercase().as_str() {
+                "APP" => format!(
+                    "APP_{}_{}",
+                    attr("PROGRAMID").unwrap_or_default(),
+                    attr("FIELDNAME").unwrap_or_default(),
+                ).to_uppercase(),
+                "USERDEF" => attr("FIELDNAME").unwrap_or_default().to_uppercase(),
+                tag => tag.to_string(),
+            };
+            let data_type = attr("TYPE")
+                .map(|data_type| data_type.to_uppercase())
+                .or_else(|| userdef_types.get(&name).cloned())
+                .filter(|data_type| !data_type.is_empty());
+            fields.push(Field { name, data_type, value });
+        }
+        if error.is_none() && !content[last..].trim().is_empty() {
+            error = Some(AdifError::InvalidElement(content_start + last));
+        }
+
+        match error {
+            Some(e) => records.push(Err(e)),
+            None if !fields.is_empty() => records.push(Ok(fields)),
+            None => {}
+        }
+    }
+    records
+}
+
+/// Write records of fields as an ADX document
+/// `APP_X_Y` fields are written as `<APP PROGRAMID="X" FIELDNAME="Y">` and fields
+/// ADIF does not define as `<USERDEF FIELDNAME="Y">`, declared in the header.
+/// Data type indicators are kept as `TYPE` attributes.
+pub fn write_document<'a>(records: impl IntoIterator<Item = Vec<(&'a str, Option<&'a str>, &'a str)>>) -> String {
+    let mut userdefs: Vec<(&str, Option<&str>)> = Vec::new();
+    let mut body = String::new();
+    for fields in records {
+        body.push_str("<RECORD>\r\n");
+        for (name, data_type, value) in fields {
+            let type_attribute = data_type
+                .map(|data_type| format!(" TYPE=\"{}\"", escape(data_type)))
+                .unwrap_or_default();
+            let app = name.strip_prefix("APP_").and_then(|rest| rest.split_once('_'));
+            match app {
+                Some((program, field)) => body.push_str(&format!(
+                    "<APP PROGRAMID=\"{}\" FIELDNAME=\"{}\"{}>{}</APP>\r\n",
+                    escape(program), escape(field), type_attribute, escape(value),
+                )),
+                None if ADIF_FIELDS.contains(&name) => {
+                    body.push_str(&format!("<{}{}>{}</{}>\r\n", name, type_attribute, escape(value), name));
+                }
+                None => {
+                    if !userdefs.iter().any(|(userdef, _)| *userdef == name) {
+                        userdefs.push((name, data_type));
+                    }
+                    body.push_str(&format!("<USERDEF FIELDNAME=\"{}\">{}</USERDEF>\r\n", escape(name), escape(value)));
+                }
+            }
+        }
+        body.push_str("</RECORD>\r\n");
+    }
+
+    let mut adx = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\r\n<ADX>\r\n<HEADER>\r\n");
+    adx.push_str(&format!("<ADIF_VER>{}</ADIF_VER>\r\n", ADIF_VERSION));
+    adx.push_str("<PROGRAMID>rs-wavelog-gate</PROGRAMID>\r\n");
+    adx.push_str(&format!("<PROGRAMVERSION>{}</PROGRAMVERSION>\r\n", env!("CARGO_PKG_VERSION")));
+    for (id, (name, data_type)) in userdefs.into_iter().enumerate() {
+        adx.push_str(&format!("<USERDEF FIELDID=\"{}\"", id + 1));
+        if let Some(data_type) = data_type {
+            adx.push_str(&format!(" TYPE=\"{}\"", escape(data_type)));
+        }
+        adx.push_str(&format!(">{}</USERDEF>\r\n", escape(name)));
+    }
+    adx.push_str("</HEADER>\r\n<RECORDS>\r\n");
+    adx.push_str(&body);
+    adx.push_str("</RECORDS>\r\n</ADX>\r\n");
+    adx
+}
+
+/// Escape the characters XML reserves
+fn escape(value: &str) -> String {
+    value.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+/// Replace the predefined XML entities and numeric character references
+pub fn unescape(value: &str) -> String {
+    let mut result = String::with_capacity(value.len());
+    let mut rest = value;
+    while let Some(start) = rest.find('&') {
+        result.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let Some(end) = rest.find(';') else {
+            break;
+        };
+        let entity = &rest[1..end];
+        let decoded = match entity {
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            "amp" => Some('&'),
+            _ => entity.strip_prefix("#x").or_else(|| entity.strip_prefix("#X"))
+                .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                .or_else(|| entity.strip_prefix('#').and_then(|dec| dec.parse().ok()))
+                .and_then(char::from_u32),
+        };
+        match decoded {
+            Some(c) => {
+                result.push(c);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                result.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    result.push_str(rest);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adif_buffer::AdifBuffer;
+
+    const DOCUMENT: &[u8] = b"<?xml version=\"1.0\"?>\r\n<ADX><HEADER><ADIF_VER>3.1.4</ADIF_VER></HEADER>\r\n\
+        <RECORDS><RECORD><CALL>DL1ABC</CALL><BAND>20m</BAND></RECORD></RECORDS></ADX>\r\n";
+
+    #[test]
+    fn detects_adx() {
+        assert!(is_adx(DOCUMENT));
+        assert!(is_adx(b"\xEF\xBB\xBF<?xml version=\"1.0\"?><ADX></ADX>"));
+        assert!(is_adx(b"  <record><CALL>DL1ABC</CALL></record>"));
+        assert!(!is_adx(b"<CALL:6>DL1ABC<EOR>"));
+        assert!(!is_adx(b"ADIF export\r\n<EOH>"));
+    }
+
+    #[test]
+    fn every_document_from_one_sender_is_adx() {
+        let mut buffer = AdifBuffer::default();
+        for _ in 0..3 {
+            let records = buffer.push(DOCUMENT).unwrap().unwrap();
+            assert!(is_adx(&records));
+            let parsed = parse(&String::from_utf8_lossy(&records));
+            assert_eq!(parsed.len(), 1);
+            assert_eq!(parsed[0].as_ref().unwrap()[0].value, "DL1ABC");
+        }
+    }
+}
@@ -0,0 +1,300 @@
+use crate::flrig::{Flrig, FlrigError};
+use crate::rigctld::{Rigctld, RigctldError};
+use crate::settings::{CatBackend, CatSettings};
+
+use iced::futures::channel::mpsc;
+use iced::futures::{SinkExt, Stream, StreamExt};
+use std::time::Duration;
+
+/// Delay before reconnecting after the CAT backend failed
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+/// Radio state reported by a CAT backend
+#[derive(Debug, Clone, PartialEq)]
+pub struct RadioStatus {
+    /// VFO frequency in Hz
+    pub frequency: u64,
+    pub mode: String,
+    /// RF power in watts, if the backend reports it
+    pub power: Option<f64>,
+}
+
+impl std::fmt::Display for RadioStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:.6} MHz {}", self.frequency as f64 / 1_000_000.0, self.mode)?;
+        if let Some(power) = self.power {
+            write!(f, " {}W", power)?;
+        }
+        Ok(())
+    }
+}
+
+/// Command sent to the radio through the running CAT poller
+#[derive(Debug, Clone)]
+pub enum CatCommand {
+    /// Tune to a frequency in Hz, optionally switching to a Wavelog mode (e.g. `FT8`, `CW`, `SSB`)
+    Tune(u64, Option<String>),
+}
+
+/// Events produced by a running CAT poller
+#[derive(Debug, Clone)]
+pub enum CatEvent {
+    /// The poller accepts commands on this channel
+    Ready(mpsc::Sender<CatCommand>),
+    /// The radio state has changed
+    Status(RadioStatus),
+    /// The radio refused a command; the connection is kept
+    CommandFailed(String),
+    /// The CAT backend could not be reached
+    Error(String),
+}
+
+/// Connection to the configured CAT backend
+#[derive(Debug)]
+enum CatClient {
+    Rigctld(Rigctld),
+    Flrig(Flrig),
+}
+
+impl CatClient {
+    /// Connect to the backend selected in the settings
+    async fn connect(settings: &CatSettings) -> Result<Self, CatError> {
+        match settings.backend {
+            CatBackend::Rigctld => Ok(CatClient::Rigctld(Rigctld::connect(&settings.host, settings.port()).await?)),
+            CatBackend::Flrig => Ok(CatClient::Flrig(Flrig::new(&settings.host, settings.port())?)),
+        }
+    }
+
+    /// Read frequency, mode and power from the radio
+    async fn poll(&mut self) -> Result<RadioStatus, CatError> {
+        match self {
+            CatClient::Rigctld(rig) => {
+                let frequency = rig.get_frequency().await?;
+                let mode = rig.get_mode().await?;
+                // Not every rig reports power, so a rejected request is not fatal
+                // Any other error leaves a late reply on the connection, which must be reset
+                let power = match rig.get_power(frequency, &mode).await {
+                    Ok(power) => Some(power),
+                    Err(RigctldError::Rejected(..)) => None,
+                    Err(e) => return Err(e.into()),
+                };
+                Ok(RadioStatus { frequency, mode, power })
+            }
+            CatClient::Flrig(rig) => {
+                let frequency = rig.get_frequency().await?;
+                let mode = rig.get_mode().await?;
+                let power = rig.get_power().await.ok();
+                Ok(RadioStatus { frequency, mode, power })
+            }
+        }
+    }
+
+    /// Execute a command on the radio
+    async fn execute(&mut self, command: CatCommand) -> Result<(), CatError> {
+        match command {
+            CatCommand::Tune(frequency, mode) => {
+                let mode = mode.map(|mode| rig_mode(&mode, frequency));
+                match self {
+                    CatClient::Rigctld(rig) => {
+                        rig.set_frequency(frequency).await?;
+                        if let Some((mode, _)) = mode {
+                            rig.set_mode(mode).await?;
+                        }
+                    }
+                    CatClient::Flrig(rig) => {
+                        rig.set_frequency(frequency).await?;
+                        if let Some((_, modes)) = mode {
+                            // Data modes are named differently per rig, e.g. `USB-D` or `DATA-U`
+                            let available = rig.get_modes().await.unwrap_or_default();
+                            let mode = modes.iter()
+                                .find(|mode| available.iter().any(|available| available == *mode))
+                                .unwrap_or(&modes[0]);
+                            rig.set_mode(mode).await?;
+                        }
+                    }
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Translate a Wavelog mode into the rigctld mode name and the FLRig mode names to try
+/// FLRig names depend on the rig, so the first one the rig offers is used
+fn rig_mode(mode: &str, frequency: u64) -> (&'static str, &'static [&'static str]) {
+    let lower_sideband = frequency < 10_000_000;
+    match mode.to_uppercase().as_str() {
+        "CW" => ("CW", &["CW"]),
+        "AM" => ("AM", &["AM"]),
+        "FM" => ("FM", &["FM"]),
+        "USB" => ("USB", &["USB"]),
+        "LSB" => ("LSB", &["LSB"]),
+        "SSB" if lower_sideband => ("LSB", &["LSB"]),
+        "SSB" => ("USB", &["USB"]),
+        // RTTY, FT8, FT4, PSK31, JS8, ... are run as USB data like WSJT-X does
+        _ => ("PKTUSB", &["USB-D", "DATA-U", "PKTUSB", "DIGU", "USB-DATA", "DATA", "USB"]),
+    }
+}
+
+/// Polls the radio through a CAT backend
+#[derive(Debug, Clone)]
+pub struct CatPoller {
+    settings: CatSettings,
+}
+
+impl CatPoller {
+    /// Create a new CAT poller instance
+    pub fn new(settings: CatSettings) -> Self {
+        Self { settings }
+    }
+
+    /// Poll the radio for the lifetime of the stream and report every change
+    /// Commands received on the channel announced with `CatEvent::Ready` are
+    /// executed between polls; the connection is re-established after errors
+    pub fn run(self) -> impl Stream<Item = CatEvent> {
+        iced::stream::channel(100, move |mut output| async move {
+            let interval = Duration::from_millis(self.settings.poll_interval);
+            let mut last_status: Option<RadioStatus> = None;
+
+            let (sender, mut commands) = mpsc::channel(16);
+            if output.send(CatEvent::Ready(sender)).await.is_err() {
+                return;
+            }
+
+            loop {
+                let error = match CatClient::connect(&self.settings).await {
+                    Ok(mut rig) => loop {
+                        match rig.poll().await {
+                            Ok(status) => {
+                                if last_status.as_ref() != Some(&status) {
+                                    last_status = Some(status.clone());
+                                    if output.send(CatEvent::Status(status)).await.is_err() {
+                                        // Receiver dropped, the subscription has been cancelled
+                                        return;
+                                    }
+                                }
+                            }
+                            Err(e) => break e.to_string(),
+                        }
+                        tokio::select! {
+                            _ = tokio::time::sleep(interval) => {}
+                            Some(command) = commands.next() => {
+                                match rig.execute(command).await {
+                                    Ok(()) => {}
+                                    Err(e) if e.needs_reconnect() => break e.to_string(),
+                                    Err(e) => {
+                                        let error = format!("QSY failed: {}", e);
+                                        if output.send(CatEvent::CommandFailed(error)).await.is_err() {
+                                            return;
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    },
+                    Err(e) => e.to_string(),
+                };
+
+                last_status = None;
+                if output.send(CatEvent::Error(error)).await.is_err() {
+                    return;
+                }
+                tokio::time::sleep(RECONNECT_DELAY).await;
+            }
+        })
+    }
+}
+
+/// CAT backend error types
+#[derive(Debug)]
+pub enum CatError {
+    Rigctld(RigctldError),
+    Flrig(FlrigError),
+}
+
+impl CatError {
+    /// Whether the connection is out of step with the backend after this error
+    /// A rigctld reply that did not arrive in time would be read as the answer to the next
+    /// command; FLRig is stateless, and refused commands leave both backends usable
+    fn needs_reconnect(&self) -> bool {
+        matches!(self, CatError::Rigctld(err) if !matches!(err, RigctldError::Rejected(..)))
+    }
+}
+
+impl From<RigctldError> for CatError {
+    fn from(err: RigctldError) -> Self {
+        CatError::Rigctld(err)
+    }
+}
+
+impl From<FlrigError> for CatError {
+    fn from(err: FlrigError) -> Self {
+        CatError::Flrig(err)
+    }
+}
+
+impl std::fmt::Display for CatError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CatError::Rigctld(err) => write!(f, "{}", err),
+            CatError::Flrig(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl std::error::Error for CatError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rigctld::tests::stand_in;
+
+    #[tokio::test]
+    async fn rigctld_power_is_optional_only_when_rejected() {
+        let port = stand_in(|command| match command {
+            "f" => Some("7074000\n"),
+            "m" => Some("LSB\n2400\n"),
+            _ => Some("RPRT -11\n"),
+        }).await;
+        let mut rig = CatClient::Rigctld(Rigctld::connect("127.0.0.1", port).await.unwrap());
+        let status = rig.poll().await.unwrap();
+        assert_eq!(status, RadioStatus { frequency: 7_074_000, mode: "LSB".to_string(), power: None });
+
+        let port = stand_in(|command| match command {
+            "f" => Some("7074000\n"),
+            "m" => Some("LSB\n2400\n"),
+            _ => None,
+        }).await;
+        let mut rig = CatClient::Rigctld(Rigctld::connect("127.0.0.1", port).await.unwrap());
+        assert!(rig.poll().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn refused_tune_keeps_the_connection() {
+        let port = stand_in(|command| match command {
+            "F 14074000" => Some("RPRT 0\n"),
+            "f" => Some("14074000\n"),
+            "m" => Some("USB\n2400\n"),
+            "l RFPOWER" => Some("RPRT -11\n"),
+            _ => Some("RPRT -1\n"),
+        }).await;
+        let mut rig = CatClient::Rigctld(Rigctld::connect("127.0.0.1", port).await.unwrap());
+        let error = rig.execute(CatCommand::Tune(14_074_000, Some("FT8".to_string()))).await.unwrap_err();
+        assert!(!error.needs_reconnect());
+        assert_eq!(rig.poll().await.unwrap().frequency, 14_074_000);
+
+        assert!(CatError::Rigctld(RigctldError::Timeout).needs_reconnect());
+        assert!(!CatError::Flrig(FlrigError::Fault("rig.set_mode".to_string())).needs_reconnect());
+    }
+
+    #[test]
+    fn modes_for_the_radio() {
+        assert_eq!(rig_mode("ssb", 7_100_000), ("LSB", &["LSB"][..]));
+        assert_eq!(rig_mode("SSB", 14_200_000), ("USB", &["USB"][..]));
+        assert_eq!(rig_mode("CW", 7_010_000).0, "CW");
+        let (rigctld, flrig) = rig_mode("FT8", 7_074_000);
+        assert_eq!(rigctld, "PKTUSB");
+        assert_eq!(flrig[0], "USB-D");
+        assert!(flrig.contains(&"DATA-U"));
+    }
+}
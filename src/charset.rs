@@ -0,0 +1,20 @@
+use encoding_rs::Encoding;
+use serde::{Deserialize, Deserializer};
+use std::borrow::Cow;
+
+/// Decode text as UTF-8, or in the fallback charset if it is not valid UTF-8
+/// Loggers on Windows commonly write names and comments in the ANSI code page
+pub fn decode<'a>(data: &'a [u8], fallback: &'static Encoding) -> Cow<'a, str> {
+    let data = data.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(data);
+    match std::str::from_utf8(data) {
+        Ok(text) => Cow::Borrowed(text),
+        Err(_) => fallback.decode_without_bom_handling(data).0,
+    }
+}
+
+/// Read a charset by its WHATWG label, e.g. `windows-1252`, `iso-8859-1` or `gbk`
+pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<&'static Encoding, D::Error> {
+    let label = String::deserialize(deserializer)?;
+    Encoding::for_label(label.trim().as_bytes())
+        .ok_or_else(|| serde::de::Error::custom(format!("unknown charset '{}'", label)))
+}
@@ -0,0 +1,166 @@
+use crate::settings::ServerSettings;
+
+use std::net::IpAddr;
+
+/// Network in CIDR notation, e.g. `192.168.1.0/24` or `fd00::/8`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct Cidr {
+    network: IpAddr,
+    prefix: u8,
+}
+
+impl Cidr {
+    /// Parse a CIDR; a plain address is treated as a single host
+    fn parse(text: &str) -> Option<Self> {
+        let (address, prefix) = match text.trim().split_once('/') {
+            Some((address, prefix)) => (address, Some(prefix.parse::<u8>().ok()?)),
+            None => (text.trim(), None),
+        };
+        let network: IpAddr = address.parse().ok()?;
+        let max_prefix = if network.is_ipv4() { 32 } else { 128 };
+        let prefix = prefix.unwrap_or(max_prefix);
+        (prefix <= max_prefix).then_some(Self { network, prefix })
+    }
+
+    /// Check whether the address lies inside this network
+    /// IPv4 addresses also match IPv6 networks in their IPv4-mapped form, e.g. `::ffff:10.0.0.0/104`
+    fn contains(&self, ip: IpAddr) -> bool {
+        let ip = match (self.network, ip) {
+            (IpAddr::V6(_), IpAddr::V4(ip)) => IpAddr::V6(ip.to_ipv6_mapped()),
+            _ => ip,
+        };
+        match (self.network, ip) {
+            (IpAddr::V4(network), IpAddr::V4(ip)) => {
+                let mask = u32::MAX.checked_shl(32 - self.prefix as u32).unwrap_or(0);
+                u32::from(network) & mask == u32::from(ip) & mask
+            }
+            (IpAddr::V6(network), IpAddr::V6(ip)) => {
+                let mask = u128::MAX.checked_shl(128 - self.prefix as u32).unwrap_or(0);
+                u128::from(network) & mask == u128::from(ip) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Source address filter for incoming log packets
+/// Denied networks always win; when an allowlist is configured, only its networks are accepted
+#[derive(Debug, Clone, Default, Hash)]
+pub struct SourceFilter {
+    allow: Vec<Cidr>,
+    deny: Vec<Cidr>,
+}
+
+impl SourceFilter {
+    /// Build the filter from the `allow` and `deny` lists in the server settings
+    pub fn from_settings(server: &ServerSettings) -> Result<Self, String> {
+        let parse = |list: &[String]| {
+            list.iter()
+                .map(|entry| Cidr::parse(entry).ok_or_else(|| format!("Invalid network '{}'", entry)))
+                .collect::<Result<Vec<_>, _>>()
+        };
+        Ok(Self { allow: parse(&server.allow)?, deny: parse(&server.deny)? })
+    }
+
+    /// Check whether packets from this address may be processed
+    pub fn is_allowed(&self, ip: IpAddr) -> bool {
+        // IPv4 senders on a dual-stack socket show up as IPv4-mapped IPv6 addresses
+        let ip = ip.to_canonical();
+        if self.deny.iter().any(|cidr| cidr.contains(ip)) {
+            return false;
+        }
+        self.allow.is_empty() || self.allow.iter().any(|cidr| cidr.contains(ip))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ip(text: &str) -> IpAddr {
+        text.parse().unwrap()
+    }
+
+    fn cidr(text: &str) -> Cidr {
+        Cidr::parse(text).unwrap()
+    }
+
+    fn filter(allow: &[&str], deny: &[&str]) -> SourceFilter {
+        let server = ServerSettings {
+            allow: allow.iter().map(|entry| entry.to_string()).collect(),
+            deny: deny.iter().map(|entry| entry.to_string()).collect(),
+            ..ServerSettings::default()
+        };
+        SourceFilter::from_settings(&server).unwrap()
+    }
+
+    #[test]
+    fn parses_networks() {
+        assert_eq!(cidr(" 192.168.1.0/24 "), Cidr { network: ip("192.168.1.0"), prefix: 24 });
+        assert_eq!(cidr("10.0.0.1"), Cidr { network: ip("10.0.0.1"), prefix: 32 });
+        assert_eq!(cidr("fd00::1"), Cidr { network: ip("fd00::1"), prefix: 128 });
+        assert_eq!(cidr("0.0.0.0/0").prefix, 0);
+        assert_eq!(cidr("::/0").prefix, 0);
+        for invalid in ["10.0.0.0/33", "fd00::/129", "10.0.0.0/", "10.0.0.0/-1", "10.0.0/8", "host/8", ""] {
+            assert_eq!(Cidr::parse(invalid), None, "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn prefix_zero_matches_every_address_of_its_family() {
+        assert!(cidr("0.0.0.0/0").contains(ip("203.0.113.7")));
+        assert!(cidr("10.1.2.3/0").contains(ip("255.255.255.255")));
+        assert!(!cidr("0.0.0.0/0").contains(ip("2001:db8::1")));
+        assert!(cidr("::/0").contains(ip("2001:db8::1")));
+    }
+
+    #[test]
+    fn full_prefix_matches_a_single_host() {
+        assert!(cidr("192.168.1.10/32").contains(ip("192.168.1.10")));
+        assert!(!cidr("192.168.1.10/32").contains(ip("192.168.1.11")));
+        assert!(cidr("2001:db8::10/128").contains(ip("2001:db8::10")));
+        assert!(!cidr("2001:db8::10/128").contains(ip("2001:db8::11")));
+    }
+
+    #[test]
+    fn prefixes_split_on_bit_boundaries() {
+        assert!(cidr("192.168.1.128/25").contains(ip("192.168.1.255")));
+        assert!(!cidr("192.168.1.128/25").contains(ip("192.168.1.127")));
+        assert!(cidr("fd00::/8").contains(ip("fdff:ffff::1")));
+        assert!(!cidr("fd00::/8").contains(ip("fe80::1")));
+    }
+
+    #[test]
+    fn ipv4_mapped_senders_match_ipv4_networks() {
+        let filter = filter(&["192.168.1.0/24"], &["192.168.1.66"]);
+        assert!(filter.is_allowed(ip("::ffff:192.168.1.10")));
+        assert!(!filter.is_allowed(ip("::ffff:192.168.2.10")));
+        assert!(!filter.is_allowed(ip("::ffff:192.168.1.66")));
+        assert!(!filter.is_allowed(ip("2001:db8::1")));
+    }
+
+    #[test]
+    fn ipv4_senders_match_ipv4_mapped_networks() {
+        let filter = filter(&["::ffff:192.168.1.0/120"], &[]);
+        assert!(filter.is_allowed(ip("192.168.1.10")));
+        assert!(filter.is_allowed(ip("::ffff:192.168.1.10")));
+        assert!(!filter.is_allowed(ip("192.168.2.10")));
+        assert!(cidr("::/0").contains(ip("10.0.0.1")));
+    }
+
+    #[test]
+    fn deny_wins_over_allow() {
+        let filter = filter(&["10.0.0.0/8"], &["10.0.0.0/24"]);
+        assert!(filter.is_allowed(ip("10.1.0.1")));
+        assert!(!filter.is_allowed(ip("10.0.0.1")));
+        assert!(!filter.is_allowed(ip("172.16.0.1")));
+        assert!(self::filter(&[], &[]).is_allowed(ip("203.0.113.7")));
+        assert!(!self::filter(&[], &["0.0.0.0/0"]).is_allowed(ip("::ffff:203.0.113.7")));
+    }
+
+    #[test]
+    fn invalid_entries_are_reported() {
+        let server = ServerSettings { allow: vec!["10.0.0.0/33".to_string()], ..ServerSettings::default() };
+        assert_eq!(SourceFilter::from_settings(&server).unwrap_err(), "Invalid network '10.0.0.0/33'");
+    }
+}
@@ -0,0 +1,146 @@
+use regex::Regex;
+use reqwest::Client;
+use std::time::Duration;
+
+/// Client for the FLRig XML-RPC interface
+#[derive(Debug, Clone)]
+pub struct Flrig {
+    client: Client,
+    url: String,
+}
+
+impl Flrig {
+    /// Create a client for FLRig listening on the given host and port
+    pub fn new(host: &str, port: u16) -> Result<Self, FlrigError> {
+        let client = Client::builder()
+            .timeout(Duration::from_secs(3))
+            .build()
+            .map_err(FlrigError::HttpError)?;
+        Ok(Self { client, url: format!("http://{}:{}/RPC2", host, port) })
+    }
+
+    /// Get the VFO frequency in Hz
+    pub async fn get_frequency(&self) -> Result<u64, FlrigError> {
+        let value = self.call("rig.get_vfo", &[]).await?;
+        value.parse::<f64>()
+            .map(|freq| freq as u64)
+            .map_err(|_| FlrigError::BadResponse(value))
+    }
+
+    /// Get the mode (e.g. `USB`, `CW`, `USB-D`)
+    pub async fn get_mode(&self) -> Result<String, FlrigError> {
+        self.call("rig.get_mode", &[]).await
+    }
+
+    /// Get the names of the modes the rig supports
+    pub async fn get_modes(&self) -> Result<Vec<String>, FlrigError> {
+        let response = self.call_raw("rig.get_modes", &[]).await?;
+        Ok(parse_values(&response))
+    }
+
+    /// Get the RF power in watts
+    pub async fn get_power(&self) -> Result<f64, FlrigError> {
+        let value = self.call("rig.get_power", &[]).await?;
+        value.parse::<f64>().map_err(|_| FlrigError::BadResponse(value))
+    }
+
+    /// Tune the VFO to a frequency in Hz
+    pub async fn set_frequency(&self, frequency: u64) -> Result<(), FlrigError> {
+        self.call("rig.set_vfo", &[format!("<double>{}</double>", frequency)]).await.map(|_| ())
+    }
+
+    /// Set the mode by its FLRig name
+    pub async fn set_mode(&self, mode: &str) -> Result<(), FlrigError> {
+        self.call("rig.set_mode", &[format!("<string>{}</string>", mode)]).await.map(|_| ())
+    }
+
+    /// Call an XML-RPC method and return its result as text
+    /// Parameters are passed as already typed XML-RPC values, e.g. `<double>14074000</double>`
+    async fn call(&self, method: &str, params: &[String]) -> Result<String, FlrigError> {
+        self.call_raw(method, params).await.map(|response| parse_value(&response))
+    }
+
+    /// Call an XML-RPC method and return the response document
+    async fn call_raw(&self, method: &str, params: &[String]) -> Result<String, FlrigError> {
+        let params: String = params.iter()
+            .map(|param| format!("<param><value>{}</value></param>", param))
+            .collect();
+        let body = format!(
+            "<?xml version=\"1.0\"?><methodCall><methodName>{}</methodName><params>{}</params></methodCall>",
+            method, params
+        );
+
+        let response = self.client
+            .post(&self.url)
+            .header(reqwest::header::CONTENT_TYPE, "text/xml")
+            .body(body)
+            .send()
+            .await
+            .map_err(FlrigError::HttpError)?
+            .text()
+            .await
+            .map_err(FlrigError::HttpError)?;
+
+        if response.contains("<fault>") {
+            return Err(FlrigError::Fault(method.to_string()));
+        }
+        Ok(response)
+    }
+}
+
+/// Extract the first value from an XML-RPC response
+/// Typed values (`<string>`, `<int>`, `<double>`, ...) and untyped values are both accepted
+fn parse_value(response: &str) -> String {
+    let re = Regex::new(r"(?s)<value>\s*(?:<(?:string|int|i4|double)>)?([^<]*)").unwrap();
+    re.captures(response)
+        .map(|cap| cap[1].trim().to_string())
+        .unwrap_or_default()
+}
+
+/// Extract all non-empty values from an XML-RPC response, e.g. the items of an array
+fn parse_values(response: &str) -> Vec<String> {
+    let re = Regex::new(r"(?s)<value>\s*(?:<(?:string|int|i4|double)>)?([^<]*)").unwrap();
+    re.captures_iter(response)
+        .map(|cap| cap[1].trim().to_string())
+        .filter(|value| !value.is_empty())
+        .collect()
+}
+
+/// FLRig client error types
+#[derive(Debug)]
+pub enum FlrigError {
+    HttpError(reqwest::Error),
+    Fault(String),
+    BadResponse(String),
+}
+
+impl std::fmt::Display for FlrigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FlrigError::HttpError(err) => write!(f, "FLRig error: {}", err),
+            FlrigError::Fault(method) => write!(f, "FLRig rejected {}", method),
+            FlrigError::BadResponse(response) => {
+                write!(f, "Unexpected FLRig response '{}'", response)
+            }
+        }
+    }
+}
+
+impl std::error::Error for FlrigError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_values_and_arrays() {
+        let response = "<?xml version=\"1.0\"?><methodResponse><params><param>\
+            <value><double>14074000</double></value></param></params></methodResponse>";
+        assert_eq!(parse_value(response), "14074000");
+
+        let response = "<?xml version=\"1.0\"?><methodResponse><params><param><value><array><data>\
+            <value>LSB</value><value>USB</value><value><string>DATA-U</string></value>\
+            </data></array></value></param></params></methodResponse>";
+        assert_eq!(parse_values(response), ["LSB", "USB", "DATA-U"]);
+    }
+}
@@ -0,0 +1,148 @@
+use iced::futures::{SinkExt, Stream};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Interval between scans of the inbox directory
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Subdirectory for files whose records were all uploaded
+pub const PROCESSED_DIR: &str = "processed";
+
+/// Subdirectory for files with at least one record that could not be uploaded
+pub const FAILED_DIR: &str = "failed";
+
+/// Watched directory for batch import of ADIF files
+#[derive(Debug, Clone)]
+pub struct Inbox {
+    dir: PathBuf,
+}
+
+/// Events produced by a watched inbox directory
+#[derive(Debug, Clone)]
+pub enum InboxEvent {
+    /// A new ADIF file is ready to be imported
+    File(PathBuf, Vec<u8>),
+    /// The directory could not be read
+    Error(String),
+}
+
+/// Outcome of importing a single inbox file
+#[derive(Debug, Clone)]
+pub struct InboxReport {
+    pub file_name: String,
+    pub uploaded: usize,
+    pub total: usize,
+    /// Directory the file was moved to, or the error that prevented it
+    pub moved_to: Result<&'static str, String>,
+}
+
+impl std::fmt::Display for InboxReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}/{} uploaded", self.file_name, self.uploaded, self.total)?;
+        match &self.moved_to {
+            Ok(dir) => write!(f, ", moved to {}", dir),
+            Err(e) => write!(f, ", not moved: {}", e),
+        }
+    }
+}
+
+impl Inbox {
+    /// Create a new inbox instance
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    /// Scan the directory for ADIF files for the lifetime of the stream
+    /// A file is reported once its size has stopped changing between two scans,
+    /// so files that are still being copied are not imported half-written
+    pub fn run(self) -> impl Stream<Item = InboxEvent> {
+        iced::stream::channel(100, move |mut output| async move {
+            if let Err(e) = tokio::fs::create_dir_all(&self.dir).await {
+                let error = format!("Failed to create inbox {}: {}", self.dir.display(), e);
+                let _ = output.send(InboxEvent::Error(error)).await;
+                return;
+            }
+
+            let mut sizes: HashMap<PathBuf, u64> = HashMap::new();
+            let mut reported: HashSet<PathBuf> = HashSet::new();
+
+            loop {
+                let files = match self.list_files().await {
+                    Ok(files) => files,
+                    Err(e) => {
+                        let error = format!("Failed to read inbox {}: {}", self.dir.display(), e);
+                        let _ = output.send(InboxEvent::Error(error)).await;
+                        Vec::new()
+                    }
+                };
+
+                // Forget files that have been moved away
+                sizes.retain(|path, _| files.iter().any(|(file, _)| file == path));
+                reported.retain(|path| files.iter().any(|(file, _)| file == path));
+
+                for (path, size) in files {
+                    if reported.contains(&path) || sizes.insert(path.clone(), size) != Some(size) {
+                        continue;
+                    }
+                    match tokio::fs::read(&path).await {
+                        Ok(data) => {
+                            reported.insert(path.clone());
+                            if output.send(InboxEvent::File(path, data)).await.is_err() {
+                                // Receiver dropped, the subscription has been cancelled
+                                return;
+                            }
+                        }
+                        Err(e) => eprintln!("Failed to read {}: {}", path.display(), e),
+                    }
+                }
+
+                tokio::time::sleep(POLL_INTERVAL).await;
+            }
+        })
+    }
+
+    /// List the ADIF files directly inside the inbox with their sizes
+    async fn list_files(&self) -> std::io::Result<Vec<(PathBuf, u64)>> {
+        let mut files = Vec::new();
+        let mut entries = tokio::fs::read_dir(&self.dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            let metadata = entry.metadata().await?;
+            if metadata.is_file() && is_adif_file(&path) {
+                files.push((path, metadata.len()));
+            }
+        }
+        Ok(files)
+    }
+}
+
+/// Check the file extension for `.adi` / `.adif` / `.adx`
+fn is_adif_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ["adi", "adif", "adx"].iter().any(|adif| ext.eq_ignore_ascii_case(adif)))
+}
+
+/// Move an imported file into `processed/` or `failed/` and write its report next to it
+/// Returns the name of the directory the file was moved to
+pub async fn finish(path: &Path, success: bool, report: &str) -> Result<&'static str, String> {
+    let subdir = if success { PROCESSED_DIR } else { FAILED_DIR };
+    let parent = path.parent().unwrap_or(Path::new("."));
+    let dir = parent.join(subdir);
+    tokio::fs::create_dir_all(&dir).await.map_err(|e| e.to_string())?;
+
+    let file_name = path.file_name().unwrap_or_default().to_string_lossy().into_owned();
+    let mut target = dir.join(&file_name);
+    let mut counter = 1;
+    while tokio::fs::try_exists(&target).await.unwrap_or(false) {
+        target = dir.join(format!("{}.{}", file_name, counter));
+        counter += 1;
+    }
+
+    tokio::fs::rename(path, &target).await.map_err(|e| e.to_string())?;
+    let mut report_path = target.into_os_string();
+    report_path.push(".log");
+    tokio::fs::write(report_path, report).await.map_err(|e| e.to_string())?;
+    Ok(subdir)
+}
@@ -0,0 +1,17 @@
+use serde::Deserialize;
+
+/// Message sent by the JS8Call UDP API
+#[derive(Debug, Deserialize)]
+struct Js8Message {
+    #[serde(rename = "type")]
+    message_type: String,
+    #[serde(default)]
+    value: String,
+}
+
+/// Extract the ADIF record from a JS8Call `LOG.QSO` message
+/// Returns `None` for any other JS8Call message
+pub fn logged_adif(text: &str) -> Option<String> {
+    let message: Js8Message = serde_json::from_str(text).ok()?;
+    (message.message_type == "LOG.QSO").then_some(message.value)
+}
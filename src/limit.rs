@@ -0,0 +1,122 @@
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::time::{Duration, Instant};
+
+/// Number of tracked sources above which idle ones are forgotten
+const MAX_SOURCES: usize = 1024;
+
+/// Token bucket of a single source
+#[derive(Debug, Clone, Copy)]
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+impl Bucket {
+    /// Tokens in the bucket after refilling it up to `now`
+    fn refilled(&self, now: Instant, rate: f64, burst: f64) -> f64 {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        (self.tokens + elapsed * rate).min(burst)
+    }
+}
+
+/// Per-source rate limit for QSOs received over the network
+/// Each source may send `burst` QSOs at once, refilled at `rate` QSOs per second
+#[derive(Debug, Clone, Default)]
+pub struct RateLimiter {
+    rate: f64,
+    burst: f64,
+    buckets: HashMap<IpAddr, Bucket>,
+}
+
+impl RateLimiter {
+    /// Create a rate limiter; a rate of 0 disables the limit
+    pub fn new(rate: f64, burst: u32) -> Self {
+        Self { rate, burst: f64::from(burst.max(1)), buckets: HashMap::new() }
+    }
+
+    /// Take one token for a QSO from this source, returning false when it is over its limit
+    pub fn allow(&mut self, ip: IpAddr) -> bool {
+        self.allow_at(ip, Instant::now())
+    }
+
+    /// Time until this source has a token for its next QSO
+    pub fn wait_time(&self, ip: IpAddr) -> Duration {
+        self.wait_time_at(ip, Instant::now())
+    }
+
+    fn allow_at(&mut self, ip: IpAddr, now: Instant) -> bool {
+        if self.rate <= 0.0 {
+            return true;
+        }
+        if self.buckets.len() >= MAX_SOURCES {
+            // Sources whose bucket has refilled completely carry no state worth keeping
+            let (rate, burst) = (self.rate, self.burst);
+            self.buckets.retain(|_, bucket| bucket.tokens + now.duration_since(bucket.updated).as_secs_f64() * rate < burst);
+        }
+
+        let bucket = self.buckets.entry(ip.to_canonical())
+            .or_insert(Bucket { tokens: self.burst, updated: now });
+        bucket.tokens = bucket.refilled(now, self.rate, self.burst);
+        bucket.updated = now;
+        if bucket.tokens < 1.0 {
+            return false;
+        }
+        bucket.tokens -= 1.0;
+        true
+    }
+
+    fn wait_time_at(&self, ip: IpAddr, now: Instant) -> Duration {
+        let Some(bucket) = self.buckets.get(&ip.to_canonical()).filter(|_| self.rate > 0.0) else {
+            return Duration::ZERO;
+        };
+        Duration::from_secs_f64((1.0 - bucket.refilled(now, self.rate, self.burst)).max(0.0) / self.rate)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: IpAddr = IpAddr::V4(std::net::Ipv4Addr::new(192, 168, 1, 10));
+
+    #[test]
+    fn burst_then_rate() {
+        let mut limiter = RateLimiter::new(2.0, 3);
+        let now = Instant::now();
+        assert!((0..3).all(|_| limiter.allow_at(SOURCE, now)));
+        assert!(!limiter.allow_at(SOURCE, now));
+        assert_eq!(limiter.wait_time_at(SOURCE, now), Duration::from_millis(500));
+
+        // One token is refilled every half second
+        let later = now + Duration::from_millis(500);
+        assert_eq!(limiter.wait_time_at(SOURCE, later), Duration::ZERO);
+        assert!(limiter.allow_at(SOURCE, later));
+        assert!(!limiter.allow_at(SOURCE, later));
+
+        // The bucket never holds more than the burst
+        let much_later = later + Duration::from_secs(60);
+        assert!((0..3).all(|_| limiter.allow_at(SOURCE, much_later)));
+        assert!(!limiter.allow_at(SOURCE, much_later));
+    }
+
+    #[test]
+    fn sources_are_limited_separately() {
+        let mut limiter = RateLimiter::new(1.0, 1);
+        let other: IpAddr = "192.168.1.11".parse().unwrap();
+        let mapped: IpAddr = "::ffff:192.168.1.10".parse().unwrap();
+        let now = Instant::now();
+        assert!(limiter.allow_at(SOURCE, now));
+        assert!(!limiter.allow_at(mapped, now));
+        assert!(limiter.allow_at(other, now));
+        assert_eq!(limiter.wait_time_at("10.0.0.1".parse().unwrap(), now), Duration::ZERO);
+    }
+
+    #[test]
+    fn zero_rate_disables_the_limit() {
+        let mut limiter = RateLimiter::new(0.0, 1);
+        let now = Instant::now();
+        assert!((0..100).all(|_| limiter.allow_at(SOURCE, now)));
+        assert_eq!(limiter.wait_time_at(SOURCE, now), Duration::ZERO);
+    }
+}
@@ -1,40 +1,198 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+mod addr;
+mod adif;
+mod adif_buffer;
+mod adx;
+mod cat;
+mod charset;
+mod filter;
+mod flrig;
+mod inbox;
+mod limit;
 mod qso;
+mod qsy;
+mod rigctld;
 mod settings;
+mod tail;
 mod wavelog;
+mod js8call;
+mod n1mm;
+mod tcp;
 mod udp;
+mod validate;
+mod wsjtx;
+mod ws;
 
+use adif::AdifError;
+use adif_buffer::AdifBuffers;
+use cat::{CatCommand, CatEvent, CatPoller, RadioStatus};
+use filter::SourceFilter;
+use inbox::{Inbox, InboxEvent, InboxReport};
+use limit::RateLimiter;
+use n1mm::N1mmAction;
 use qso::QSO;
-use settings::Settings;
-use wavelog::send;
-use udp::UdpListener;
+use qsy::{QsyEvent, QsyServer};
+use settings::{ListenerProtocol, ListenerSettings, Settings};
+use wavelog::{send, send_radio, WavelogError};
+use tail::{FileTail, TailEvent};
+use tcp::{TcpAdifListener, TcpEvent};
+use udp::{UdpEvent, UdpListener};
+use validate::Validation;
+use wsjtx::WsjtxMessage;
+use ws::{WsBroadcaster, WsEvent, WsServer};
 
+use iced::futures::channel::mpsc;
 use iced::widget::{Column, Container, Text, Scrollable, Row, Space, Tooltip};
-use iced::{Color, Element, Length, Task};
+use iced::{Color, Element, Length, Subscription, Task};
+use std::collections::{HashMap, VecDeque};
+use std::net::{IpAddr, SocketAddr};
+use std::path::PathBuf;
+use std::time::Duration;
 
 /// Maximum number of QSO records to keep in memory
 const MAX_LOG_LINES: usize = 500;
 
+/// Number of recently logged WSJT-X QSOs remembered for duplicate detection
+const MAX_RECENT_WSJTX_QSOS: usize = 16;
+
+/// Time to wait for the Logged ADIF (type 12) of a QSO reported as QSO Logged (type 5)
+const WSJTX_ADIF_WAIT: Duration = Duration::from_secs(2);
+
+/// Upload status shown for QSOs that were deleted in the logging program
+const STATUS_DELETED: &str = "Deleted";
+
+/// QSO record shown in the log table
+#[derive(Debug, Clone)]
+struct QsoRecord {
+    qso: QSO,
+    status: String,             // Upload status
+    source_id: Option<String>,  // Contact ID assigned by the logging program, if any
+    warnings: Vec<String>,      // ADIF data-type warnings found before upload
+}
+
+/// QSO waiting for a free upload slot
+#[derive(Debug)]
+struct PendingUpload {
+    qso: QSO,
+    source_id: Option<String>,
+    warnings: Vec<String>,
+    settings: Settings,
+    origin: Origin,
+    throttled: bool, // Held back by the per-source rate limit at least once
+}
+
+/// Where a QSO came from
+#[derive(Debug, Clone, Copy)]
+enum Origin {
+    /// Sender of network input, which is subject to the rate limit
+    Network(IpAddr),
+    /// Chunk of records read from the tailed log file with the given index
+    Tail { index: usize, chunk: u64 },
+}
+
+/// Records read from a tailed log file
+/// The offset past them is stored once every record has an upload result
+#[derive(Debug)]
+struct TailChunk {
+    id: u64,
+    /// File offset just past the records
+    offset: u64,
+    /// Uploads still queued or running
+    pending: usize,
+    /// An upload failed without an answer from the Wavelog API
+    failed: bool,
+}
+
+/// Remove the leading chunks whose uploads are all done and return the offset past them
+/// Chunks after one that is still uploading or failed are kept, even if they are done
+fn pop_done_chunks(chunks: &mut VecDeque<TailChunk>) -> Option<u64> {
+    let mut offset = None;
+    while let Some(chunk) = chunks.front().filter(|chunk| chunk.pending == 0 && !chunk.failed) {
+        offset = Some(chunk.offset);
+        chunks.pop_front();
+    }
+    offset
+}
+
 /// Main application state
 /// Manages QSO records, status information, and settings
 #[derive(Debug)]
 struct RustWavelogGateApp {
-    qso_records: Vec<(QSO, String)>, // QSO records with their upload status
+    qso_records: Vec<QsoRecord>,     // QSO records with their upload status
     status_message: String,          // Current status message for status bar
     listen_info: String,             // Connection information display
+    radio_info: String,              // Current radio status display
+    wsjtx_info: String,              // Dial frequency and DX call reported by WSJT-X
+    wsjtx_radio: Option<RadioStatus>, // Radio status last sent on behalf of WSJT-X
+    cat_commands: Option<mpsc::Sender<CatCommand>>, // Command channel of the running CAT poller
+    ws_broadcaster: Option<WsBroadcaster>, // Event feed of the running WebSocket server
     settings: Option<Settings>,      // Application configuration
+    listeners: Vec<ListenerSettings>, // Listeners started from the configuration
+    recent_wsjtx_qsos: Vec<String>,  // Keys of QSOs already logged via WSJT-X type 5/12
+    wsjtx_pending: HashMap<String, (QSO, IpAddr, Settings)>, // Type 5 QSOs waiting for their type 12
+    adif_buffers: AdifBuffers<(usize, SocketAddr)>, // Incomplete ADIF per listener and sender
+    source_filter: SourceFilter,     // Allow/deny lists for packet sources
+    rejected_packets: u64,           // Packets and TCP connections dropped by the source filter
+    last_rejected: Option<SocketAddr>, // Sender of the most recently rejected packet
+    upload_queue: VecDeque<PendingUpload>, // QSOs waiting for upload
+    uploads_in_flight: usize,        // Uploads currently running
+    rate_limiter: RateLimiter,       // Per-source QSO rate limit
+    throttled_qsos: u64,             // QSOs held back by the per-source rate limit
+    last_throttled: Option<String>,  // Source of the most recently throttled QSO
+    upload_timer: bool,              // A wake-up for throttled uploads is scheduled
+    tail_chunks: HashMap<usize, VecDeque<TailChunk>>, // Records per tailed file whose offset is not stored yet
+    next_tail_chunk: u64,            // ID of the next chunk read from a tailed file
 }
 
 /// Application messages for event handling
+#[allow(clippy::enum_variant_names)]
 #[derive(Debug, Clone)]
 enum Message {
     /// Settings have been loaded from configuration file
     SettingsLoaded(Result<Settings, String>),
-    /// QSO has been processed and sent to Wavelog
-    QSOReceived(QSO, String),
-    /// Raw UDP message received from ham radio software
-    UdpMessage(Vec<u8>),
+    /// QSO has been processed and sent to Wavelog; the flag is false unless Wavelog itself answered
+    QSOReceived(QsoRecord, Origin, bool),
+    /// Raw UDP message received from ham radio software on the given listener
+    UdpMessage(usize, SocketAddr, Vec<u8>),
+    /// UDP listener failed to start
+    UdpError(String),
+    /// Complete ADIF record received over TCP on the given listener
+    TcpMessage(usize, SocketAddr, Vec<u8>),
+    /// TCP connection from a source rejected by the source filter has been closed
+    TcpRejected(SocketAddr),
+    /// TCP listener failed to start
+    TcpError(String),
+    /// ADIF records appended to the tailed log file with the given index, and the offset past them
+    TailMessage(usize, Vec<u8>, u64),
+    /// Tailed log file could not be read
+    TailError(String),
+    /// ADIF file found in the inbox directory
+    InboxFile(PathBuf, Vec<u8>),
+    /// Inbox directory could not be read
+    InboxError(String),
+    /// Inbox file has been imported and moved
+    InboxFileDone(InboxReport, Vec<QsoRecord>),
+    /// Radio state changed according to the CAT backend
+    CatStatus(RadioStatus),
+    /// CAT backend could not be reached
+    CatError(String),
+    /// Radio refused a tune or mode change
+    CatCommandFailed(String),
+    /// CAT poller is running and accepts commands
+    CatReady(mpsc::Sender<CatCommand>),
+    /// Tune request received on the QSY endpoint
+    QsyRequest(u64, Option<String>),
+    /// Radio status has been sent to Wavelog
+    RadioSent(Result<(), String>),
+    /// WebSocket server is running and accepts events to publish
+    WsReady(WsBroadcaster),
+    /// WebSocket server failed to start
+    WsError(String),
+    /// A throttled source may have a token for its next upload again
+    UploadTimer,
+    /// No Logged ADIF arrived for the WSJT-X QSO with this key, so its QSO Logged report is used
+    WsjtxAdifTimeout(String),
 }
 
 impl RustWavelogGateApp {
@@ -45,7 +203,27 @@ impl RustWavelogGateApp {
             qso_records: Vec::new(),
             status_message: "Loading...".to_string(),
             listen_info: String::new(),
+            radio_info: String::new(),
+            wsjtx_info: String::new(),
+            wsjtx_radio: None,
+            cat_commands: None,
+            ws_broadcaster: None,
             settings: None,
+            listeners: Vec::new(),
+            recent_wsjtx_qsos: Vec::new(),
+            wsjtx_pending: HashMap::new(),
+            adif_buffers: AdifBuffers::default(),
+            source_filter: SourceFilter::default(),
+            rejected_packets: 0,
+            last_rejected: None,
+            upload_queue: VecDeque::new(),
+            uploads_in_flight: 0,
+            rate_limiter: RateLimiter::default(),
+            throttled_qsos: 0,
+            last_throttled: None,
+            upload_timer: false,
+            tail_chunks: HashMap::new(),
+            next_tail_chunk: 0,
         };
         
         let task = Task::perform(Self::load_settings(), Message::SettingsLoaded);
@@ -57,106 +235,461 @@ impl RustWavelogGateApp {
         Settings::load().map_err(|e| format!("Configuration loading failed: {}", e))
     }
 
-    /// Start UDP listener and wait for one message
-    /// Used for continuous listening by restarting after each message
-    async fn start_udp_listener(host: String, port: u16) -> Vec<u8> {
-        let listener = UdpListener::new(host, port);
-        listener.listen_once().await.unwrap_or_default()
-    }
-
     /// Add a new QSO record to the beginning of the list
+    /// Records carrying a known source ID replace the existing row in place
     /// Maintains a maximum number of records by removing the oldest ones
-    fn add_qso_record(&mut self, qso: QSO, status: String) {
-        self.qso_records.insert(0, (qso, status)); // Insert at the beginning, new QSO at the top
+    fn add_qso_record(&mut self, record: QsoRecord) {
+        if let Some(existing) = self.find_qso_record(record.source_id.as_deref()) {
+            *existing = record;
+            return;
+        }
+        self.qso_records.insert(0, record); // Insert at the beginning, new QSO at the top
         if self.qso_records.len() > MAX_LOG_LINES {
             self.qso_records.pop(); // Remove the last (oldest) record
         }
     }
 
+    /// Find the record logged with the given source ID
+    fn find_qso_record(&mut self, source_id: Option<&str>) -> Option<&mut QsoRecord> {
+        let source_id = source_id.filter(|id| !id.is_empty())?;
+        self.qso_records.iter_mut()
+            .find(|record| record.source_id.as_deref() == Some(source_id))
+    }
+
     /// Convert status text to display format
-    /// Maps "OK" to "OK", everything else to "Error"
+    /// Maps "OK" and "Deleted" to themselves, everything else to "Error"
     fn get_status_display(status: &str) -> &str {
-        if status == "OK" {
-            "OK"
-        } else {
-            "Error"
+        match status {
+            "OK" => "OK",
+            STATUS_DELETED => STATUS_DELETED,
+            _ => "Error",
         }
     }
 
     /// Process QSO data received from UDP and send to Wavelog
-    /// Parses ADIF format and creates tasks for each valid QSO
-    fn process_qso_data(&self, data: &[u8], settings: &Settings) -> Task<Message> {
-        let adif = match std::str::from_utf8(data) {
-            Ok(adif) => adif,
-            Err(_) => return self.restart_udp_listener(settings),
-        };
+    /// Decodes the datagram according to the listener protocol and creates tasks for each valid QSO
+    /// ADIF text is reassembled per listener and sender, so records split over datagrams are kept
+    fn process_qso_data(&mut self, source: (usize, SocketAddr), data: &[u8], protocol: ListenerProtocol, settings: &Settings) -> Task<Message> {
+        // WSJT-X binary messages are recognised by their magic number
+        if protocol == ListenerProtocol::Wsjtx
+            || (protocol == ListenerProtocol::Auto && wsjtx::is_wsjtx(data)) {
+            return self.process_wsjtx_data(data, source.1.ip(), settings);
+        }
+
+        // Text that is not valid UTF-8 is read in the configured charset
+        let text = charset::decode(data, settings.server.charset);
+
+        match protocol {
+            ListenerProtocol::N1mm => self.process_n1mm_data(&text, source.1.ip(), settings),
+            ListenerProtocol::Auto if n1mm::is_n1mm(&text) => self.process_n1mm_data(&text, source.1.ip(), settings),
+            ListenerProtocol::Js8call => match js8call::logged_adif(&text) {
+                Some(adif) => self.process_adif(adif.as_bytes(), Origin::Network(source.1.ip()), settings),
+                None => Task::none(),
+            },
+            _ => {
+                // ADIF may be split anywhere, even inside a multi-byte character,
+                // so raw bytes are buffered and only complete records are decoded
+                match self.adif_buffers.push(source, data) {
+                    Ok(Some(records)) => self.process_adif(&records, Origin::Network(source.1.ip()), settings),
+                    Ok(None) => Task::none(),
+                    Err(e) => {
+                        self.status_message = format!("{}: {}", source.1, e);
+                        Task::none()
+                    }
+                }
+            }
+        }
+    }
 
-        let qsos = QSO::from_adif(adif);
+    /// Parse ADIF and queue each valid QSO for upload
+    fn process_adif(&mut self, adif: &[u8], origin: Origin, settings: &Settings) -> Task<Message> {
+        let qsos = self.parse_adif(adif, settings);
         let mut tasks = Vec::new();
 
         // Process each QSO record
         for qso in qsos {
-            if self.is_valid_qso(&qso) {
-                tasks.push(self.send_qso_task(qso, settings.clone()));
-            }
+            tasks.push(self.submit_qso(qso, None, origin, settings.clone()));
         }
 
-        // Restart UDP listener for next message
-        tasks.push(self.restart_udp_listener(settings));
         Task::batch(tasks)
     }
 
-    /// Validate if a QSO record has all required fields
-    /// Checks for non-empty call sign, date, time, and band
-    fn is_valid_qso(&self, qso: &QSO) -> bool {
-        !qso.call.is_empty() && !qso.qso_date.is_empty() 
-            && !qso.time_on.is_empty() && !qso.band.is_empty()
+    /// Parse ADI or ADX data, showing malformed records in the status bar
+    fn parse_adif(&mut self, adif: &[u8], settings: &Settings) -> Vec<QSO> {
+        QSO::from_log(adif, settings.server.charset)
+            .into_iter()
+            .filter_map(|record| record.map_err(|e| self.status_message = e.to_string()).ok())
+            .collect()
     }
 
-    /// Create an async task to send QSO to Wavelog
-    /// Returns success/error status for UI display
-    fn send_qso_task(&self, qso: QSO, settings: Settings) -> Task<Message> {
-        Task::perform(
-            async move {
-                let status = match send(&qso, &settings).await {
-                    Ok(_) => "OK".to_string(),
-                    Err(e) => format!("{}", e),
-                };
-                (qso, status)
-            },
-            |(qso, status)| Message::QSOReceived(qso, status),
-        )
+    /// Process an N1MM Logger+ contact broadcast
+    /// Replaced contacts are uploaded again and update their existing row;
+    /// deleted contacts are only marked in the table
+    fn process_n1mm_data(&mut self, xml: &str, source: IpAddr, settings: &Settings) -> Task<Message> {
+        let Some(contact) = n1mm::parse(xml) else {
+            return Task::none();
+        };
+
+        match contact.action {
+            N1mmAction::Info | N1mmAction::Replace => {
+                self.submit_qso(contact.qso, Some(contact.id), Origin::Network(source), settings.clone())
+            }
+            N1mmAction::Delete => {
+                if let Some(record) = self.find_qso_record(Some(&contact.id)) {
+                    record.status = STATUS_DELETED.to_string();
+                    self.status_message = format!("QSO {} deleted in N1MM", record.qso.call);
+                }
+                Task::none()
+            }
+        }
     }
 
-    /// Restart UDP listener to wait for next message
-    fn restart_udp_listener(&self, settings: &Settings) -> Task<Message> {
-        Task::perform(
-            Self::start_udp_listener(settings.server.host.clone(), settings.server.port),
-            Message::UdpMessage,
-        )
+    /// Process a WSJT-X binary message and send logged QSOs to Wavelog
+    /// WSJT-X reports every logged QSO twice: QSO Logged (type 5) first, then Logged ADIF
+    /// (type 12) with the exact record it wrote to its log. The type 12 record is uploaded;
+    /// a type 5 report is only used if no type 12 follows within `WSJTX_ADIF_WAIT`
+    fn process_wsjtx_data(&mut self, data: &[u8], source: IpAddr, settings: &Settings) -> Task<Message> {
+        match wsjtx::decode(data) {
+            Ok(WsjtxMessage::Heartbeat(heartbeat)) => {
+                self.status_message = format!("{} v{} connected", heartbeat.id, heartbeat.version);
+                Task::none()
+            }
+            Ok(WsjtxMessage::QsoLogged(logged)) => {
+                let qso = logged.to_qso();
+                let key = Self::wsjtx_key(&qso);
+                if self.recent_wsjtx_qsos.contains(&key) || self.wsjtx_pending.contains_key(&key) {
+                    return Task::none();
+                }
+                self.wsjtx_pending.insert(key.clone(), (qso, source, settings.clone()));
+                Task::perform(
+                    async move {
+                        tokio::time::sleep(WSJTX_ADIF_WAIT).await;
+                        key
+                    },
+                    Message::WsjtxAdifTimeout,
+                )
+            }
+            Ok(WsjtxMessage::LoggedAdif(adif)) => {
+                let mut tasks = Vec::new();
+                for qso in self.parse_adif(adif.as_bytes(), settings) {
+                    let key = Self::wsjtx_key(&qso);
+                    self.wsjtx_pending.remove(&key);
+                    if self.remember_wsjtx_qso(key) {
+                        tasks.push(self.submit_qso(qso, None, Origin::Network(source), settings.clone()));
+                    }
+                }
+                Task::batch(tasks)
+            }
+            Ok(WsjtxMessage::Status(status)) => self.handle_wsjtx_status(status, settings),
+            Ok(WsjtxMessage::Other(_)) => Task::none(),
+            Err(e) => {
+                self.status_message = e.to_string();
+                Task::none()
+            }
+        }
+    }
+
+    /// Key identifying a QSO reported by WSJT-X as type 5 and type 12
+    fn wsjtx_key(qso: &QSO) -> String {
+        format!("{}|{}|{}", qso.call.to_uppercase(), qso.qso_date, qso.time_on)
+    }
+
+    /// Remember a logged WSJT-X QSO, returning false if it has been logged already
+    fn remember_wsjtx_qso(&mut self, key: String) -> bool {
+        if self.recent_wsjtx_qsos.contains(&key) {
+            return false;
+        }
+        self.recent_wsjtx_qsos.push(key);
+        if self.recent_wsjtx_qsos.len() > MAX_RECENT_WSJTX_QSOS {
+            self.recent_wsjtx_qsos.remove(0);
+        }
+        true
+    }
+
+    /// Upload a WSJT-X QSO Logged report whose Logged ADIF did not arrive
+    fn handle_wsjtx_adif_timeout(&mut self, key: String) -> Task<Message> {
+        let Some((qso, source, settings)) = self.wsjtx_pending.remove(&key) else {
+            return Task::none();
+        };
+        if !self.remember_wsjtx_qso(key) {
+            return Task::none();
+        }
+        self.submit_qso(qso, None, Origin::Network(source), settings)
+    }
+
+    /// Handle WSJT-X Status by showing the dial frequency and DX call
+    /// Without a CAT backend, frequency and mode changes are also sent to Wavelog as radio status
+    fn handle_wsjtx_status(&mut self, status: wsjtx::Status, settings: &Settings) -> Task<Message> {
+        let radio = status.to_radio_status();
+        self.wsjtx_info = format!("{} {}", status.id, radio);
+        if !status.dx_call.is_empty() {
+            self.wsjtx_info += &format!(" DX: {}", status.dx_call);
+        }
+        if status.transmitting {
+            self.wsjtx_info += " (TX)";
+        }
+
+        if settings.cat.is_some() || self.wsjtx_radio.as_ref() == Some(&radio) {
+            return Task::none();
+        }
+        self.wsjtx_radio = Some(radio.clone());
+        self.radio_update_task(radio, status.id, settings.clone())
+    }
+
+    /// Complete a received QSO and check it against the ADIF data types
+    /// Bands left out by the logger are derived from the frequency and modes
+    /// are mapped to ADIF MODE / SUBMODE pairs first
+    fn check_qso(&self, qso: &mut QSO) -> Validation {
+        qso.fill_bands();
+        qso.normalize_mode(self.settings.as_ref().map(|settings| settings.mode_map.as_slice()).unwrap_or_default());
+        validate::validate(qso)
+    }
+
+    /// Validate a QSO against the ADIF data types and queue it for upload
+    /// QSOs with errors are not uploaded but shown in the table with their errors
+    fn submit_qso(&mut self, mut qso: QSO, source_id: Option<String>, origin: Origin, settings: Settings) -> Task<Message> {
+        let Validation { errors, warnings } = self.check_qso(&mut qso);
+        if errors.is_empty() {
+            return self.queue_upload(qso, source_id, origin, warnings, settings);
+        }
+
+        let status = errors.join("; ");
+        self.status_message = format!("QSO {} not uploaded: {}", qso.call, status);
+        if !qso.call.is_empty() {
+            self.add_qso_record(QsoRecord { qso, status, source_id, warnings });
+        }
+        Task::none()
+    }
+
+    /// Queue a QSO for upload to Wavelog
+    /// Network QSOs beyond `upload.max_queue` are dropped and listed in the table;
+    /// QSOs from local files always wait for a free upload slot
+    fn queue_upload(&mut self, qso: QSO, source_id: Option<String>, origin: Origin, warnings: Vec<String>, settings: Settings) -> Task<Message> {
+        let network_queued = self.upload_queue.iter()
+            .filter(|pending| matches!(pending.origin, Origin::Network(_)))
+            .count();
+        if matches!(origin, Origin::Network(_)) && network_queued >= settings.upload.max_queue {
+            let status = "Dropped: upload queue full".to_string();
+            self.status_message = format!("QSO {} dropped: upload queue full", qso.call);
+            self.add_qso_record(QsoRecord { qso, status, source_id, warnings });
+            return Task::none();
+        }
+
+        if let Some(chunk) = self.tail_chunk(origin) {
+            chunk.pending += 1;
+        }
+        self.upload_queue.push_back(PendingUpload { qso, source_id, warnings, settings, origin, throttled: false });
+        self.start_uploads()
+    }
+
+    /// Start queued uploads while fewer than `upload.concurrency` are running
+    /// Network QSOs wait in the queue until their source is within its rate limit,
+    /// without holding back QSOs from other sources
+    /// Each upload reports its success/error status for UI display
+    fn start_uploads(&mut self) -> Task<Message> {
+        let mut tasks = Vec::new();
+        let mut wait: Option<Duration> = None;
+        let mut index = 0;
+        while let Some(pending) = self.upload_queue.get_mut(index) {
+            if self.uploads_in_flight >= pending.settings.upload.concurrency.max(1) {
+                break;
+            }
+            if let Origin::Network(ip) = pending.origin
+                && !self.rate_limiter.allow(ip) {
+                if !pending.throttled {
+                    pending.throttled = true;
+                    self.throttled_qsos += 1;
+                    self.last_throttled = Some(ip.to_canonical().to_string());
+                }
+                let delay = self.rate_limiter.wait_time(ip);
+                wait = Some(wait.map_or(delay, |wait| wait.min(delay)));
+                index += 1;
+                continue;
+            }
+            let Some(PendingUpload { qso, source_id, warnings, settings, origin, .. }) = self.upload_queue.remove(index) else {
+                break;
+            };
+            self.uploads_in_flight += 1;
+            tasks.push(Task::perform(
+                async move {
+                    // Only an answer from the Wavelog API itself means the QSO reached it;
+                    // transport errors, server errors and HTML pages of a proxy do not
+                    let (status, reached) = match send(&qso, &settings).await {
+                        Ok(_) => ("OK".to_string(), true),
+                        Err(e) => (format!("{}", e), WavelogError::is_rejection(e.as_ref())),
+                    };
+                    (QsoRecord { qso, status, source_id, warnings }, origin, reached)
+                },
+                |(record, origin, reached)| Message::QSOReceived(record, origin, reached),
+            ));
+        }
+
+        // Throttled QSOs are retried once their source has a token again
+        if let Some(wait) = wait
+            && !self.upload_timer {
+            self.upload_timer = true;
+            tasks.push(Task::perform(tokio::time::sleep(wait), |_| Message::UploadTimer));
+        }
+        Task::batch(tasks)
+    }
+
+    /// Handle the wake-up for throttled uploads
+    fn handle_upload_timer(&mut self) -> Task<Message> {
+        self.upload_timer = false;
+        self.start_uploads()
+    }
+
+    /// Keep every configured listener and tailed file running for as long as settings are loaded
+    /// Subscriptions are keyed by their configuration, so sockets stay open
+    /// across updates and every datagram or record is delivered as a message
+    pub fn subscription(&self) -> Subscription<Message> {
+        let tails = self.settings.iter()
+            .flat_map(|settings| settings.tails.iter().enumerate())
+            .map(|(index, tail)| {
+                Subscription::run_with_id(
+                    tail.clone(),
+                    FileTail::new(tail.path.clone().into(), tail.from_start).run(),
+                )
+                .with(index)
+                .map(|(index, event)| match event {
+                    TailEvent::Records(adif, offset) => Message::TailMessage(index, adif, offset),
+                    TailEvent::Error(e) => Message::TailError(e),
+                })
+            });
+
+        let listeners = self.listeners.iter().enumerate().map(|(index, listener)| {
+            let id = (listener.clone(), self.source_filter.clone());
+            match listener.protocol {
+                ListenerProtocol::Tcp => Subscription::run_with_id(
+                    id,
+                    TcpAdifListener::new(listener.host.clone(), listener.port)
+                        .with_dual_stack(listener.dual_stack)
+                        .with_filter(self.source_filter.clone())
+                        .run(),
+                )
+                .with(index)
+                .map(|(index, event)| match event {
+                    TcpEvent::Record(src, adif) => Message::TcpMessage(index, src, adif),
+                    TcpEvent::Rejected(src) => Message::TcpRejected(src),
+                    TcpEvent::Error(e) => Message::TcpError(e),
+                }),
+                _ => Subscription::run_with_id(
+                    id,
+                    UdpListener::new(listener.host.clone(), listener.port)
+                        .with_multicast(listener.multicast.clone())
+                        .with_dual_stack(listener.dual_stack)
+                        .with_forward(listener.forward.clone(), self.source_filter.clone())
+                        .run(),
+                )
+                .with(index)
+                .map(|(index, event)| match event {
+                    UdpEvent::Datagram(src, data) => Message::UdpMessage(index, src, data),
+                    UdpEvent::Error(e) => Message::UdpError(e),
+                }),
+            }
+        });
+
+        let inbox = match self.settings.as_ref().and_then(|settings| settings.inbox.as_ref()) {
+            Some(inbox) => Subscription::run_with_id(
+                inbox.clone(),
+                Inbox::new(inbox.path.clone().into()).run(),
+            )
+            .map(|event| match event {
+                InboxEvent::File(path, data) => Message::InboxFile(path, data),
+                InboxEvent::Error(e) => Message::InboxError(e),
+            }),
+            None => Subscription::none(),
+        };
+
+        let cat = match self.settings.as_ref().and_then(|settings| settings.cat.as_ref()) {
+            Some(cat) => Subscription::run_with_id(cat.clone(), CatPoller::new(cat.clone()).run())
+                .map(|event| match event {
+                    CatEvent::Ready(commands) => Message::CatReady(commands),
+                    CatEvent::Status(status) => Message::CatStatus(status),
+                    CatEvent::CommandFailed(e) => Message::CatCommandFailed(e),
+                    CatEvent::Error(e) => Message::CatError(e),
+                }),
+            None => Subscription::none(),
+        };
+
+        let qsy = match self.settings.as_ref().and_then(|settings| settings.cat.as_ref()) {
+            Some(cat) if cat.qsy_port != 0 => Subscription::run_with_id(
+                ("qsy", cat.qsy_port),
+                QsyServer::new(cat.qsy_port).run(),
+            )
+            .map(|event| match event {
+                QsyEvent::Tune(frequency, mode) => Message::QsyRequest(frequency, mode),
+                QsyEvent::Error(e) => Message::CatError(e),
+            }),
+            _ => Subscription::none(),
+        };
+
+        let ws = match self.settings.as_ref().map(|settings| settings.server.ws_port) {
+            Some(port) if port != 0 => Subscription::run_with_id(("ws", port), WsServer::new(port).run())
+                .map(|event| match event {
+                    WsEvent::Ready(broadcaster) => Message::WsReady(broadcaster),
+                    WsEvent::Error(e) => Message::WsError(e),
+                }),
+            _ => Subscription::none(),
+        };
+
+        Subscription::batch(listeners.chain(tails).chain([inbox, cat, qsy, ws]))
     }
 
     /// Main message dispatcher for handling app events
     pub fn update(&mut self, message: Message) -> Task<Message> {
         match message {
             Message::SettingsLoaded(result) => self.handle_settings_loaded(result),
-            Message::UdpMessage(data) => self.handle_udp_message(data),
-            Message::QSOReceived(qso, status) => self.handle_qso_received(qso, status),
+            Message::UdpMessage(index, src, data) => self.handle_udp_message(index, src, data),
+            Message::QSOReceived(record, origin, reached) => self.handle_qso_received(record, origin, reached),
+            Message::UdpError(e) | Message::TcpError(e) | Message::TailError(e) | Message::InboxError(e)
+            | Message::WsError(e) | Message::CatCommandFailed(e) => {
+                self.handle_listener_error(e)
+            }
+            Message::InboxFile(path, data) => self.handle_inbox_file(path, data),
+            Message::InboxFileDone(report, records) => self.handle_inbox_file_done(report, records),
+            Message::CatStatus(status) => self.handle_cat_status(status),
+            Message::CatError(e) => self.handle_cat_error(e),
+            Message::CatReady(commands) => self.handle_cat_ready(commands),
+            Message::QsyRequest(frequency, mode) => self.handle_qsy_request(frequency, mode),
+            Message::RadioSent(result) => self.handle_radio_sent(result),
+            Message::WsReady(broadcaster) => self.handle_ws_ready(broadcaster),
+            Message::TailMessage(index, adif, offset) => self.handle_tail_message(index, adif, offset),
+            Message::TcpMessage(index, src, adif) => self.handle_tcp_message(index, src, adif),
+            Message::TcpRejected(src) => self.handle_tcp_rejected(src),
+            Message::UploadTimer => self.handle_upload_timer(),
+            Message::WsjtxAdifTimeout(key) => self.handle_wsjtx_adif_timeout(key),
         }
     }
 
     /// Handle settings loaded event
-    /// Updates UI with connection info and starts UDP listener
+    /// Updates UI with connection info; listener subscriptions start once settings are stored
     fn handle_settings_loaded(&mut self, result: Result<Settings, String>) -> Task<Message> {
         match result {
             Ok(settings) => {
-                self.listen_info = format!("Listen: {}:{} | Wavelog: {}", 
-                    settings.server.host, settings.server.port, settings.wavelog.url);
+                self.source_filter = match SourceFilter::from_settings(&settings.server) {
+                    Ok(filter) => filter,
+                    Err(e) => {
+                        self.status_message = format!("Config load failed: {}", e);
+                        self.listen_info = String::new();
+                        return Task::none();
+                    }
+                };
+                self.listeners = settings.effective_listeners();
+                self.rate_limiter = RateLimiter::new(settings.upload.rate, settings.upload.burst);
+                let listeners: Vec<String> = self.listeners.iter()
+                    .map(|listener| match listener.protocol {
+                        ListenerProtocol::Auto => addr::display(&listener.host, listener.port),
+                        protocol => format!("{} ({})", addr::display(&listener.host, listener.port), protocol),
+                    } + &listener.multicast.as_ref()
+                        .map(|multicast| format!(" @{}", multicast.group))
+                        .unwrap_or_default())
+                    .collect();
+                self.listen_info = format!("Listen: {} | Wavelog: {}",
+                    listeners.join(", "), settings.wavelog.url);
                 self.status_message = "Ready".to_string();
-                
-                let task = self.restart_udp_listener(&settings);
                 self.settings = Some(settings);
-                task
+                Task::none()
             }
             Err(e) => {
                 self.status_message = format!("Config load failed: {}", e);
@@ -166,27 +699,268 @@ impl RustWavelogGateApp {
         }
     }
 
+    /// Settings to use for QSOs received on the listener with the given index
+    fn listener_settings(&self, index: usize) -> Option<(ListenerProtocol, Settings)> {
+        let settings = self.settings.as_ref()?;
+        let listener = self.listeners.get(index)?;
+        Some((listener.protocol, settings.for_station(listener.station.as_ref())))
+    }
+
+    /// Check the sender against the source filter, counting rejected packets
+    fn accept_source(&mut self, src: SocketAddr) -> bool {
+        if self.source_filter.is_allowed(src.ip()) {
+            return true;
+        }
+        self.count_rejected(src);
+        false
+    }
+
+    /// Count a packet or connection rejected by the source filter
+    fn count_rejected(&mut self, src: SocketAddr) {
+        self.rejected_packets += 1;
+        self.last_rejected = Some(src);
+    }
+
     /// Handle incoming UDP message with QSO data
-    fn handle_udp_message(&mut self, data: Vec<u8>) -> Task<Message> {
-        match &self.settings {
-            Some(settings) => self.process_qso_data(&data, settings),
+    fn handle_udp_message(&mut self, index: usize, src: SocketAddr, data: Vec<u8>) -> Task<Message> {
+        if !self.accept_source(src) {
+            return Task::none();
+        }
+        match self.listener_settings(index) {
+            Some((protocol, settings)) => self.process_qso_data((index, src), &data, protocol, &settings),
             None => Task::none(),
         }
     }
 
-    /// Handle completed QSO processing result
-    /// Updates the QSO record list and status message
-    fn handle_qso_received(&mut self, qso: QSO, status: String) -> Task<Message> {
-        if !qso.call.is_empty() {
-            self.add_qso_record(qso, status);
-            self.status_message = "QSO processed".to_string();
+    /// Handle complete ADIF record received over TCP
+    fn handle_tcp_message(&mut self, index: usize, src: SocketAddr, adif: Vec<u8>) -> Task<Message> {
+        if !self.accept_source(src) {
+            return Task::none();
+        }
+        match self.listener_settings(index) {
+            Some((_, settings)) => self.process_adif(&adif, Origin::Network(src.ip()), &settings),
+            None => Task::none(),
+        }
+    }
+
+    /// Handle a TCP connection closed by the source filter by counting it as rejected
+    fn handle_tcp_rejected(&mut self, src: SocketAddr) -> Task<Message> {
+        self.count_rejected(src);
+        Task::none()
+    }
+
+    /// Handle ADIF records appended to a tailed log file
+    /// The offset past the records is stored once all of them have been uploaded
+    fn handle_tail_message(&mut self, index: usize, adif: Vec<u8>, offset: u64) -> Task<Message> {
+        let Some(settings) = &self.settings else {
+            return Task::none();
+        };
+        let Some(tail) = settings.tails.get(index) else {
+            return Task::none();
+        };
+        let settings = settings.for_station(tail.station.as_ref());
+
+        let chunk = self.next_tail_chunk;
+        self.next_tail_chunk += 1;
+        self.tail_chunks.entry(index).or_default()
+            .push_back(TailChunk { id: chunk, offset, pending: 0, failed: false });
+        let task = self.process_adif(&adif, Origin::Tail { index, chunk }, &settings);
+        // Chunks without any QSO to upload are done right away
+        self.store_tail_offset(index);
+        task
+    }
+
+    /// Chunk of a tailed log file that a QSO was read from
+    fn tail_chunk(&mut self, origin: Origin) -> Option<&mut TailChunk> {
+        let Origin::Tail { index, chunk } = origin else {
+            return None;
+        };
+        self.tail_chunks.get_mut(&index)?.iter_mut().find(|pending| pending.id == chunk)
+    }
+
+    /// Store the offset of a tailed log file past its leading chunks that are done
+    /// A chunk with an upload that did not reach Wavelog keeps the offset before it,
+    /// so its records are read again on the next start
+    fn store_tail_offset(&mut self, index: usize) {
+        let Some(chunks) = self.tail_chunks.get_mut(&index) else {
+            return;
+        };
+        let offset = pop_done_chunks(chunks);
+        let tail = self.settings.as_ref().and_then(|settings| settings.tails.get(index));
+        if let (Some(offset), Some(tail)) = (offset, tail) {
+            tail::store_offset(&tail.path, offset);
+        }
+    }
+
+    /// Handle an ADIF file found in the inbox
+    /// All records are uploaded one after another, then the file is moved to
+    /// `processed/` if every record succeeded or to `failed/` otherwise
+    fn handle_inbox_file(&mut self, path: PathBuf, data: Vec<u8>) -> Task<Message> {
+        let Some(settings) = &self.settings else {
+            return Task::none();
+        };
+        let station = settings.inbox.as_ref().and_then(|inbox| inbox.station.as_ref());
+        let settings = settings.for_station(station);
+
+        let qsos: Vec<Result<(QSO, Validation), AdifError>> = QSO::from_log(&data, settings.server.charset)
+            .into_iter()
+            .map(|record| record.map(|mut qso| {
+                let validation = self.check_qso(&mut qso);
+                (qso, validation)
+            }))
+            .collect();
+        let file_name = path.file_name().unwrap_or_default().to_string_lossy().into_owned();
+        self.status_message = format!("Importing {} ({} QSOs)", file_name, qsos.len());
+
+        Task::perform(
+            async move {
+                let total = qsos.len();
+                let mut records = Vec::new();
+                let mut lines = Vec::new();
+                for record in qsos {
+                    // Malformed records count as failed and are listed in the report
+                    let (qso, Validation { errors, warnings }) = match record {
+                        Ok(record) => record,
+                        Err(e) => {
+                            lines.push(e.to_string());
+                            continue;
+                        }
+                    };
+                    let status = if !errors.is_empty() {
+                        errors.join("; ")
+                    } else {
+                        match send(&qso, &settings).await {
+                            Ok(_) => "OK".to_string(),
+                            Err(e) => format!("{}", e),
+                        }
+                    };
+                    let mut line = format!("{} {} {} {}", qso.call, qso.qso_date, qso.time_on, status);
+                    if !warnings.is_empty() {
+                        line += &format!(" (warnings: {})", warnings.join("; "));
+                    }
+                    lines.push(line);
+                    records.push(QsoRecord { qso, status, source_id: None, warnings });
+                }
+
+                let uploaded = records.iter().filter(|record| record.status == "OK").count();
+                let success = total > 0 && uploaded == total;
+                lines.push(format!("{}/{} uploaded", uploaded, total));
+                let moved_to = inbox::finish(&path, success, &lines.join("\n")).await;
+                (InboxReport { file_name, uploaded, total, moved_to }, records)
+            },
+            |(report, records)| Message::InboxFileDone(report, records),
+        )
+    }
+
+    /// Handle completed inbox import
+    /// Adds the imported records to the table and shows the per-file report
+    fn handle_inbox_file_done(&mut self, report: InboxReport, records: Vec<QsoRecord>) -> Task<Message> {
+        for record in records.into_iter().filter(|record| !record.qso.call.is_empty()) {
+            self.add_qso_record(record);
+        }
+        self.status_message = report.to_string();
+        Task::none()
+    }
+
+    /// Handle radio state change by updating the status bar and Wavelog
+    fn handle_cat_status(&mut self, status: RadioStatus) -> Task<Message> {
+        let Some(settings) = self.settings.clone() else {
+            return Task::none();
+        };
+        let radio = settings.cat.as_ref().map(|cat| cat.radio.clone()).unwrap_or_default();
+        self.radio_info = status.to_string();
+        self.radio_update_task(status, radio, settings)
+    }
+
+    /// Publish a radio status on the WebSocket feed and send it to Wavelog
+    fn radio_update_task(&self, status: RadioStatus, radio: String, settings: Settings) -> Task<Message> {
+        if let Some(broadcaster) = &self.ws_broadcaster {
+            broadcaster.radio_status(&status, &radio);
+        }
+
+        Task::perform(
+            async move {
+                send_radio(&status, &radio, &settings).await
+                    .map(|_| ())
+                    .map_err(|e| format!("Radio update failed: {}", e))
+            },
+            Message::RadioSent,
+        )
+    }
+
+    /// Handle CAT backend failure by showing it in the status bar
+    fn handle_cat_error(&mut self, error: String) -> Task<Message> {
+        self.radio_info = String::new();
+        self.status_message = error;
+        Task::none()
+    }
+
+    /// Handle CAT poller start by keeping its command channel
+    fn handle_cat_ready(&mut self, commands: mpsc::Sender<CatCommand>) -> Task<Message> {
+        self.cat_commands = Some(commands);
+        Task::none()
+    }
+
+    /// Handle tune request from Wavelog by forwarding it to the CAT backend
+    fn handle_qsy_request(&mut self, frequency: u64, mode: Option<String>) -> Task<Message> {
+        let target = format!("{:.6} MHz {}", frequency as f64 / 1_000_000.0, mode.as_deref().unwrap_or_default());
+        let sent = self.cat_commands.as_mut()
+            .is_some_and(|commands| commands.try_send(CatCommand::Tune(frequency, mode)).is_ok());
+        self.status_message = if sent {
+            format!("QSY {}", target.trim_end())
+        } else {
+            format!("QSY {} ignored: CAT not connected", target.trim_end())
+        };
+        Task::none()
+    }
+
+    /// Handle the result of sending the radio status to Wavelog
+    fn handle_radio_sent(&mut self, result: Result<(), String>) -> Task<Message> {
+        if let Err(e) = result {
+            self.status_message = e;
         }
         Task::none()
     }
 
+    /// Handle WebSocket server start by keeping its event feed
+    fn handle_ws_ready(&mut self, broadcaster: WsBroadcaster) -> Task<Message> {
+        self.ws_broadcaster = Some(broadcaster);
+        Task::none()
+    }
+
+    /// Handle listener failure by showing it in the status bar
+    fn handle_listener_error(&mut self, error: String) -> Task<Message> {
+        self.status_message = error;
+        Task::none()
+    }
+
+    /// Handle completed QSO processing result
+    /// Updates the QSO record list and status message, then starts the next queued upload
+    fn handle_qso_received(&mut self, record: QsoRecord, origin: Origin, reached: bool) -> Task<Message> {
+        self.uploads_in_flight = self.uploads_in_flight.saturating_sub(1);
+        if let Some(chunk) = self.tail_chunk(origin) {
+            chunk.pending = chunk.pending.saturating_sub(1);
+            chunk.failed |= !reached;
+        }
+        if let Origin::Tail { index, .. } = origin {
+            self.store_tail_offset(index);
+        }
+        if !record.qso.call.is_empty() {
+            if let Some(broadcaster) = &self.ws_broadcaster {
+                broadcaster.qso_logged(&record.qso, &record.status);
+            }
+            self.add_qso_record(record);
+            self.status_message = match self.upload_queue.len() {
+                0 => "QSO processed".to_string(),
+                queued => format!("QSO processed, {} queued", queued),
+            };
+        }
+        self.start_uploads()
+    }
+
     /// Build the application's user interface
     /// Creates a table layout with QSO records, header, and status bar
-    pub fn view(&self) -> Element<Message> {
+    pub fn view(&self) -> Element<'_, Message> {
         // Create sticky table header with column titles
         let sticky_header = Container::new(
             Row::new()
@@ -216,23 +990,31 @@ impl RustWavelogGateApp {
         // Create scrollable table rows for QSO data
         let mut data_rows = Vec::new();
         
-        for (qso, status) in &self.qso_records {
-            // Set status color: green for OK, red for errors
-            let status_color = if status == "OK" { 
-                Color::from_rgb(0.0, 0.8, 0.0) 
-            } else { 
-                Color::from_rgb(0.8, 0.0, 0.0) 
+        for QsoRecord { qso, status, warnings, .. } in &self.qso_records {
+            // Set status color: green for OK, amber for OK with warnings, grey for deleted, red for errors
+            let status_color = match status.as_str() {
+                "OK" if !warnings.is_empty() => Color::from_rgb(0.9, 0.6, 0.0),
+                "OK" => Color::from_rgb(0.0, 0.8, 0.0),
+                STATUS_DELETED => Color::from_rgb(0.6, 0.6, 0.6),
+                _ => Color::from_rgb(0.8, 0.0, 0.0),
             };
 
-            let status_display = Self::get_status_display(status);
+            let status_display = match status.as_str() {
+                "OK" if !warnings.is_empty() => "Warning",
+                status => Self::get_status_display(status),
+            };
             
-            // Create status element with tooltip for error details
-            let status_element: Element<Message> = if status == "OK" {
+            // Create status element with tooltip for error details and warnings
+            let status_element: Element<Message> = if status == "OK" && warnings.is_empty() {
                 Text::new(status_display).width(Length::Fixed(100.0)).size(12).color(status_color).font(iced::Font::MONOSPACE).into()
             } else {
+                let details = std::iter::once(status.as_str())
+                    .chain(warnings.iter().map(String::as_str))
+                    .collect::<Vec<_>>()
+                    .join("\n");
                 Tooltip::new(
                     Text::new(status_display).width(Length::Fixed(100.0)).size(12).color(status_color).font(iced::Font::MONOSPACE),
-                    Text::new(status.as_str()).size(11),
+                    Text::new(details).size(11),
                     iced::widget::tooltip::Position::Top
                 )
                 .style(|_theme| {
@@ -260,7 +1042,7 @@ impl RustWavelogGateApp {
                     .push(Text::new(&qso.call).width(Length::Fixed(120.0)).size(12).color(Color::WHITE).font(iced::Font::MONOSPACE))
                     .push(Text::new(&qso.gridsquare).width(Length::Fixed(60.0)).size(12).color(Color::WHITE).font(iced::Font::MONOSPACE))
                     .push(Text::new(&qso.band).width(Length::Fixed(50.0)).size(12).color(Color::WHITE).font(iced::Font::MONOSPACE))
-                    .push(Text::new(&qso.mode).width(Length::Fixed(50.0)).size(12).color(Color::WHITE).font(iced::Font::MONOSPACE))
+                    .push(Text::new(if qso.submode.is_empty() { &qso.mode } else { &qso.submode }).width(Length::Fixed(50.0)).size(12).color(Color::WHITE).font(iced::Font::MONOSPACE))
                     .push(Text::new(format!("{}/{}", qso.rst_sent, qso.rst_rcvd)).width(Length::Fixed(64.0)).size(12).color(Color::WHITE).font(iced::Font::MONOSPACE))
                     .push(status_element)
                     .padding(10)
@@ -319,7 +1101,23 @@ impl RustWavelogGateApp {
         let status_bar = Container::new(
             Row::new()
                 .push(Text::new(&self.listen_info).size(12).color(Color::from_rgb(0.7, 0.7, 0.7)))
+                .push_maybe((!self.radio_info.is_empty()).then(|| {
+                    Text::new(format!(" | Radio: {}", self.radio_info)).size(12).color(Color::from_rgb(0.7, 0.7, 0.7))
+                }))
+                .push_maybe((!self.wsjtx_info.is_empty()).then(|| {
+                    Text::new(format!(" | {}", self.wsjtx_info)).size(12).color(Color::from_rgb(0.7, 0.7, 0.7))
+                }))
                 .push(Space::with_width(Length::Fill))
+                .push_maybe(self.last_throttled.as_ref().map(|src| {
+                    Text::new(format!("Throttled: {} (last {})  ", self.throttled_qsos, src))
+                        .size(12)
+                        .color(Color::from_rgb(0.9, 0.5, 0.0))
+                }))
+                .push_maybe(self.last_rejected.map(|src| {
+                    Text::new(format!("Rejected: {} (last {})  ", self.rejected_packets, src.ip()))
+                        .size(12)
+                        .color(Color::from_rgb(0.9, 0.5, 0.0))
+                }))
                 .push(Text::new(&self.status_message).size(12).color(Color::from_rgb(0.9, 0.9, 0.9)))
                 .padding(8)
                 .width(Length::Fill)
@@ -364,7 +1162,27 @@ impl Default for RustWavelogGateApp {
             qso_records: Vec::new(),
             status_message: "Starting...".to_string(),
             listen_info: String::new(),
+            radio_info: String::new(),
+            wsjtx_info: String::new(),
+            wsjtx_radio: None,
+            cat_commands: None,
+            ws_broadcaster: None,
             settings: None,
+            listeners: Vec::new(),
+            recent_wsjtx_qsos: Vec::new(),
+            wsjtx_pending: HashMap::new(),
+            adif_buffers: AdifBuffers::default(),
+            source_filter: SourceFilter::default(),
+            rejected_packets: 0,
+            last_rejected: None,
+            upload_queue: VecDeque::new(),
+            uploads_in_flight: 0,
+            rate_limiter: RateLimiter::default(),
+            throttled_qsos: 0,
+            last_throttled: None,
+            upload_timer: false,
+            tail_chunks: HashMap::new(),
+            next_tail_chunk: 0,
         }
     }
 }
@@ -391,6 +1209,7 @@ fn main() -> iced::Result {
         RustWavelogGateApp::update,
         RustWavelogGateApp::view
     )
+        .subscription(RustWavelogGateApp::subscription)
         .theme(|_state| iced::Theme::Dark)
         .window(iced::window::Settings {
             size: iced::Size {
@@ -408,3 +1227,121 @@ fn main() -> iced::Result {
         })
         .run_with(RustWavelogGateApp::new)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunk(id: u64, offset: u64, pending: usize) -> TailChunk {
+        TailChunk { id, offset, pending, failed: false }
+    }
+
+    fn record(call: &str, status: &str) -> QsoRecord {
+        let qso = QSO { call: call.to_string(), ..QSO::default() };
+        QsoRecord { qso, status: status.to_string(), source_id: None, warnings: Vec::new() }
+    }
+
+    fn settings(max_queue: usize) -> Settings {
+        Settings {
+            wavelog: settings::WaveLogSettings {
+                url: "http://127.0.0.1:9".to_string(),
+                key: String::new(),
+                station: String::new(),
+            },
+            server: Default::default(),
+            listeners: Vec::new(),
+            tails: Vec::new(),
+            inbox: None,
+            cat: None,
+            upload: settings::UploadSettings { max_queue, ..Default::default() },
+            mode_map: Vec::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn throttled_qsos_wait_and_only_a_full_queue_drops() {
+        let mut app = RustWavelogGateApp { rate_limiter: RateLimiter::new(1.0, 1), ..Default::default() };
+        let sender = Origin::Network("192.168.1.10".parse().unwrap());
+        let other = Origin::Network("192.168.1.11".parse().unwrap());
+        for call in ["DL1A", "DL1B", "DL1C"] {
+            let _ = app.queue_upload(record(call, "").qso, None, sender, Vec::new(), settings(2));
+        }
+        // The first QSO is uploaded, the others wait for a token instead of being dropped
+        assert_eq!(app.uploads_in_flight, 1);
+        assert_eq!(app.upload_queue.len(), 2);
+        assert_eq!(app.throttled_qsos, 2);
+        assert!(app.upload_timer);
+        assert!(app.qso_records.is_empty());
+
+        // Beyond max_queue a QSO is dropped and listed in the table
+        let _ = app.queue_upload(record("DL1D", "").qso, None, other, Vec::new(), settings(2));
+        assert_eq!(app.upload_queue.len(), 2);
+        assert_eq!(app.qso_records.len(), 1);
+        assert_eq!(app.qso_records[0].qso.call, "DL1D");
+        assert!(app.qso_records[0].status.starts_with("Dropped"));
+
+        // Another source is not held back by the throttled one
+        let _ = app.queue_upload(record("DL1E", "").qso, None, other, Vec::new(), settings(3));
+        assert_eq!(app.uploads_in_flight, 2);
+        assert_eq!(app.upload_queue.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn wsjtx_logged_adif_is_uploaded_instead_of_qso_logged() {
+        use wsjtx::tests::{logged_adif, LOGGED_ADIF, QSO_LOGGED};
+
+        let settings = settings(10);
+        let source: IpAddr = "127.0.0.1".parse().unwrap();
+        let expected = QSO::from_adif(LOGGED_ADIF.as_bytes(), encoding_rs::UTF_8).remove(0).unwrap();
+        // Every upload slot is busy, so submitted QSOs stay in the queue
+        let mut app = RustWavelogGateApp { uploads_in_flight: 4, ..Default::default() };
+
+        let _ = app.process_wsjtx_data(QSO_LOGGED, source, &settings);
+        assert!(app.upload_queue.is_empty());
+        let _ = app.process_wsjtx_data(&logged_adif(LOGGED_ADIF), source, &settings);
+        let _ = app.process_wsjtx_data(QSO_LOGGED, source, &settings);
+        let _ = app.handle_wsjtx_adif_timeout("DL1ABC|20240315|123400".to_string());
+        assert_eq!(app.upload_queue.len(), 1);
+        assert_eq!(app.upload_queue[0].qso.fields, expected.fields);
+
+        // Without a Logged ADIF, the QSO Logged report is uploaded once the wait is over
+        let mut app = RustWavelogGateApp { uploads_in_flight: 4, ..Default::default() };
+        let _ = app.process_wsjtx_data(QSO_LOGGED, source, &settings);
+        let _ = app.handle_wsjtx_adif_timeout("DL1ABC|20240315|123400".to_string());
+        let _ = app.process_wsjtx_data(QSO_LOGGED, source, &settings);
+        assert_eq!(app.upload_queue.len(), 1);
+        assert_eq!(app.upload_queue[0].qso.call, "DL1ABC");
+        assert!(app.wsjtx_pending.is_empty());
+    }
+
+    #[test]
+    fn offset_stops_before_unfinished_chunks() {
+        let mut chunks = VecDeque::from([chunk(0, 100, 0), chunk(1, 200, 1), chunk(2, 300, 0)]);
+        assert_eq!(pop_done_chunks(&mut chunks), Some(100));
+        assert_eq!(chunks.len(), 2);
+        assert_eq!(pop_done_chunks(&mut chunks), None);
+
+        chunks[0].pending = 0;
+        assert_eq!(pop_done_chunks(&mut chunks), Some(300));
+        assert!(chunks.is_empty());
+    }
+
+    #[test]
+    fn offset_is_kept_before_uploads_that_did_not_reach_wavelog() {
+        let mut app = RustWavelogGateApp::default();
+        app.tail_chunks.insert(0, VecDeque::from([chunk(0, 100, 2), chunk(1, 200, 1)]));
+
+        let _ = app.handle_qso_received(record("DL1A", "OK"), Origin::Tail { index: 0, chunk: 0 }, true);
+        assert_eq!(app.tail_chunks[&0].len(), 2);
+        let _ = app.handle_qso_received(record("DL1B", "Wrong URL"), Origin::Tail { index: 0, chunk: 0 }, false);
+        let _ = app.handle_qso_received(record("DL1C", "OK"), Origin::Tail { index: 0, chunk: 1 }, true);
+        // The failed chunk holds back the offset of every chunk after it
+        assert_eq!(app.tail_chunks[&0].len(), 2);
+        assert!(app.tail_chunks[&0][0].failed);
+        assert_eq!(pop_done_chunks(app.tail_chunks.get_mut(&0).unwrap()), None);
+
+        app.tail_chunks.insert(1, VecDeque::from([chunk(2, 50, 1)]));
+        let _ = app.handle_qso_received(record("DL1D", "duplicate"), Origin::Tail { index: 1, chunk: 2 }, true);
+        assert!(app.tail_chunks[&1].is_empty());
+    }
+}
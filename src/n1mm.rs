@@ -0,0 +1,248 @@
+use crate::adx::unescape;
+use crate::qso::{self, QSO};
+
+use regex::Regex;
+use std::collections::HashMap;
+
+/// Kind of contact event broadcast by N1MM Logger+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum N1mmAction {
+    /// `<contactinfo>`: a new contact has been logged
+    Info,
+    /// `<contactreplace>`: an existing contact has been edited
+    Replace,
+    /// `<contactdelete>`: an existing contact has been deleted
+    Delete,
+}
+
+/// Contact event received from N1MM Logger+
+#[derive(Debug, Clone)]
+pub struct N1mmContact {
+    pub action: N1mmAction,
+    /// N1MM contact ID, used to match replace and delete events to the original contact
+    pub id: String,
+    pub qso: QSO,
+}
+
+/// Check whether a datagram is an N1MM Logger+ XML broadcast
+pub fn is_n1mm(text: &str) -> bool {
+    let text = text.trim_start();
+    let text = match text.strip_prefix("<?xml") {
+        Some(rest) => rest.split_once("?>").map(|(_, body)| body.trim_start()).unwrap_or_default(),
+        None => text,
+    };
+    ["<contactinfo", "<contactreplace", "<contactdelete", "<RadioInfo", "<AppInfo", "<spot", "<lookupinfo"]
+        .iter()
+        .any(|tag| text.starts_with(tag))
+}
+
+/// Parse an N1MM Logger+ contact broadcast
+/// Returns `None` for broadcasts that do not describe a contact (radio info, spots, ...)
+pub fn parse(text: &str) -> Option<N1mmContact> {
+    let action = if text.contains("<contactinfo") {
+        N1mmAction::Info
+    } else if text.contains("<contactreplace") {
+        N1mmAction::Replace
+    } else if text.contains("<contactdelete") {
+        N1mmAction::Delete
+    } else {
+        return None;
+    };
+
+    let re = Regex::new(r"<([A-Za-z0-9_]+)>([^<]*)</[A-Za-z0-9_]+>").unwrap();
+    let elements: Vec<(String, String)> = re.captures_iter(text)
+        .map(|cap| (cap[1].to_lowercase(), unescape(cap[2].trim())))
+        .collect();
+    let fields: HashMap<String, String> = elements.iter().cloned().collect();
+    let field = |name: &str| fields.get(name).cloned().unwrap_or_default();
+
+    // Frequencies are sent in units of 10 Hz
+    let txfreq = field("txfreq").parse::<u64>().ok().map(|f| f * 10);
+    let rxfreq = field("rxfreq").parse::<u64>().ok().map(|f| f * 10);
+    let (qso_date, time_on) = split_timestamp(&field("timestamp"));
+
+    let mut qso = QSO {
+        call: field("call"),
+        gridsquare: field("gridsquare"),
+        mode: field("mode"),
+        rst_sent: field("snt"),
+        rst_rcvd: field("rcv"),
+        qso_date,
+        time_on,
+        band: txfreq.or(rxfreq)
+            .and_then(qso::band_from_hz)
+            .unwrap_or_default()
+            .to_string(),
+        freq: txfreq.map(format_mhz).unwrap_or_default(),
+        freq_rx: rxfreq.map(format_mhz).unwrap_or_default(),
+        operator: field("operator"),
+        station_callsign: field("mycall"),
+        contest_id: field("contestname"),
+        srx_string: field("exchange1"),
+        comment: field("comment"),
+        ..Default::default()
+    };
+
+    // Keep the remaining elements, as ADIF fields where there is one, otherwise as APP_N1MM_*
+    for (name, value) in elements {
+        if TYPED_ELEMENTS.contains(&name.as_str()) {
+            continue;
+        }
+        match ADIF_ELEMENTS.iter().find(|(element, _)| *element == name) {
+            // Serial numbers and the check are 0 in contests without them
+            Some((_, adif)) if matches!(*adif, "STX" | "SRX" | "CHECK") => {
+                if value != "0" {
+                    qso.push_field(adif, value);
+                }
+            }
+            Some((_, adif)) => qso.push_field(adif, value),
+            None => qso.push_field(&format!("APP_N1MM_{}", name), value),
+        }
+    }
+
+    Some(N1mmContact { action, id: field("id"), qso })
+}
+
+/// Elements already converted into typed QSO fields
+const TYPED_ELEMENTS: &[&str] = &[
+    "call", "gridsquare", "mode", "snt", "rcv", "timestamp", "txfreq", "rxfreq",
+    "operator", "mycall", "contestname", "exchange1", "comment",
+];
+
+/// Elements with an ADIF field of their own
+const ADIF_ELEMENTS: &[(&str, &str)] = &[
+    ("name", "NAME"),
+    ("qth", "QTH"),
+    ("power", "TX_PWR"),
+    ("sntnr", "STX"),
+    ("rcvnr", "SRX"),
+    ("section", "ARRL_SECT"),
+    ("prec", "PRECEDENCE"),
+    ("ck", "CHECK"),
+];
+
+/// Format a frequency in Hz as MHz for ADIF
+fn format_mhz(hz: u64) -> String {
+    format!("{:.6}", hz as f64 / 1_000_000.0)
+}
+
+/// Split an N1MM timestamp (`YYYY-MM-DD HH:MM:SS`) into ADIF date and time
+fn split_timestamp(timestamp: &str) -> (String, String) {
+    let digits = |s: &str| s.chars().filter(char::is_ascii_digit).collect::<String>();
+    match timestamp.split_once(' ') {
+        Some((date, time)) => (digits(date), digits(time)),
+        None => (String::new(), String::new()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Contact broadcast in the layout documented for N1MM Logger+, some elements left out
+    const CONTACT_INFO: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<contactinfo>
+	<app>N1MM</app>
+	<contestname>CWOPS</contestname>
+	<contestnr>73</contestnr>
+	<timestamp>2020-01-17 16:43:38</timestamp>
+	<mycall>W2XYZ</mycall>
+	<band>3.5</band>
+	<rxfreq>352519</rxfreq>
+	<txfreq>352519</txfreq>
+	<operator></operator>
+	<mode>CW</mode>
+	<call>W1AW</call>
+	<countryprefix>K</countryprefix>
+	<wpxprefix>W1</wpxprefix>
+	<stationprefix>W2XYZ</stationprefix>
+	<continent>NA</continent>
+	<snt>599</snt>
+	<sntnr>5</sntnr>
+	<rcv>599</rcv>
+	<rcvnr>0</rcvnr>
+	<gridsquare></gridsquare>
+	<exchange1>HIRAM</exchange1>
+	<section></section>
+	<comment></comment>
+	<qth></qth>
+	<name>Hiram</name>
+	<power>100</power>
+	<misctext></misctext>
+	<zone>0</zone>
+	<prec></prec>
+	<ck>0</ck>
+	<ismultiplier1>0</ismultiplier1>
+	<points>1</points>
+	<radionr>1</radionr>
+	<IsOriginal>False</IsOriginal>
+	<StationName>CONTEST-PC</StationName>
+	<ID>f9ffac4fcd3e479ca86e137df1338531</ID>
+	<IsClaimedQso>1</IsClaimedQso>
+</contactinfo>"#;
+
+    fn field<'a>(qso: &'a QSO, name: &str) -> Option<&'a str> {
+        qso.fields.iter().find(|field| field.name == name).map(|field| field.value.as_str())
+    }
+
+    #[test]
+    fn contact_info() {
+        assert!(is_n1mm(CONTACT_INFO));
+        let contact = parse(CONTACT_INFO).unwrap();
+        assert_eq!(contact.action, N1mmAction::Info);
+        assert_eq!(contact.id, "f9ffac4fcd3e479ca86e137df1338531");
+
+        let qso = contact.qso;
+        assert_eq!(qso.call, "W1AW");
+        assert_eq!(qso.mode, "CW");
+        assert_eq!((qso.qso_date.as_str(), qso.time_on.as_str()), ("20200117", "164338"));
+        // Frequencies are sent in units of 10 Hz
+        assert_eq!(qso.freq, "3.525190");
+        assert_eq!(qso.freq_rx, "3.525190");
+        assert_eq!(qso.band, "80m");
+        assert_eq!((qso.rst_sent.as_str(), qso.rst_rcvd.as_str()), ("599", "599"));
+        assert_eq!(qso.station_callsign, "W2XYZ");
+        assert_eq!(qso.contest_id, "CWOPS");
+        assert_eq!(qso.srx_string, "HIRAM");
+        assert_eq!(field(&qso, "NAME"), Some("Hiram"));
+        assert_eq!(field(&qso, "TX_PWR"), Some("100"));
+        assert_eq!(field(&qso, "STX"), Some("5"));
+        assert_eq!(field(&qso, "APP_N1MM_ISCLAIMEDQSO"), Some("1"));
+        // Zero serial numbers and checks of contests without them are left out
+        assert_eq!(field(&qso, "SRX"), None);
+        assert_eq!(field(&qso, "CHECK"), None);
+        assert_eq!(field(&qso, "QTH"), None);
+    }
+
+    #[test]
+    fn replace_and_delete_carry_the_contact_id() {
+        let replace = CONTACT_INFO.replace("contactinfo>", "contactreplace>").replace("<ck>0", "<ck>72");
+        let contact = parse(&replace).unwrap();
+        assert_eq!(contact.action, N1mmAction::Replace);
+        assert_eq!(contact.id, "f9ffac4fcd3e479ca86e137df1338531");
+        assert_eq!(field(&contact.qso, "CHECK"), Some("72"));
+
+        let delete = r#"<?xml version="1.0" encoding="utf-8"?>
+<contactdelete>
+	<app>N1MM</app>
+	<timestamp>2020-01-17 16:43:38</timestamp>
+	<call>W1AW</call>
+	<contestnr>73</contestnr>
+	<StationName>CONTEST-PC</StationName>
+	<ID>f9ffac4fcd3e479ca86e137df1338531</ID>
+</contactdelete>"#;
+        let contact = parse(delete).unwrap();
+        assert_eq!(contact.action, N1mmAction::Delete);
+        assert_eq!(contact.id, "f9ffac4fcd3e479ca86e137df1338531");
+    }
+
+    #[test]
+    fn other_broadcasts_are_not_contacts() {
+        let radio = "<?xml version=\"1.0\"?><RadioInfo><Freq>1407400</Freq></RadioInfo>";
+        assert!(is_n1mm(radio));
+        assert!(parse(radio).is_none());
+        assert!(!is_n1mm("<CALL:4>W1AW<EOR>"));
+        assert_eq!(split_timestamp("2020-01-17 16:43:38"), ("20200117".to_string(), "164338".to_string()));
+        assert_eq!(split_timestamp(""), (String::new(), String::new()));
+    }
+}
@@ -1,6 +1,11 @@
-use regex::Regex;
+use crate::adif::{self, AdifError, Field};
+use crate::adx;
+use crate::settings::ModeMapping;
+
+use encoding_rs::Encoding;
 use std::collections::HashMap;
 
+#[allow(clippy::upper_case_acronyms)]
 #[derive(Debug, Clone, Default)]
 pub struct QSO {
     pub call: String,
@@ -14,6 +19,7 @@ pub struct QSO {
     pub qso_date_off: String,
     pub time_off: String,
     pub band: String,
+    pub band_rx: String,
     pub freq: String,
     pub freq_rx: String,
     pub operator: String,
@@ -21,6 +27,10 @@ pub struct QSO {
     pub power: String,
     pub my_gridsquare: String,
     pub station_callsign: String,
+    pub contest_id: String,
+    pub srx_string: String,
+    /// Every field as received, in original order, including those without a typed field above
+    pub fields: Vec<Field>,
 }
 
 impl QSO {
@@ -60,6 +70,7 @@ impl QSO {
             qso_date_off: map.get("qso_date_off").cloned().unwrap_or_default(),
             time_off: map.get("time_off").cloned().unwrap_or_default(),
             band: map.get("band").cloned().unwrap_or_default(),
+            band_rx: map.get("band_rx").cloned().unwrap_or_default(),
             freq: map.get("freq").cloned().unwrap_or_default(),
             freq_rx: map.get("freq_rx").cloned().unwrap_or_default(),
             operator: map.get("operator").cloned().unwrap_or_default(),
@@ -67,51 +78,388 @@ impl QSO {
             power: map.get("power").cloned().unwrap_or_default(),
             my_gridsquare: map.get("my_gridsquare").cloned().unwrap_or_default(),
             station_callsign: map.get("station_callsign").cloned().unwrap_or_default(),
+            contest_id: map.get("contest_id").cloned().unwrap_or_default(),
+            srx_string: map.get("srx_string").cloned().unwrap_or_default(),
+            fields: Vec::new(),
         }
     }
 
-    pub fn from_adif(input: &str) -> Vec<Self> {
-        let mut qsos = Vec::new();
-        let mut current = HashMap::new();
-        let re = Regex::new(r"(?i)<([a-z_]+)(?::(\d+))?(?::([a-z]+))?>([^<]*)").unwrap();
-        for cap in re.captures_iter(input) {
-            let field = cap[1].to_uppercase();
-            let value = cap[4].trim().to_string();
-            if field == "EOR" {
-                qsos.push(Self::from_map(&current));
-                current.clear();
-                continue;
+    /// Parse every record of an ADIF document
+    /// Malformed records are returned as errors in their place; values that are
+    /// not valid UTF-8 are decoded in `charset`
+    pub fn from_adif(input: &[u8], charset: &'static Encoding) -> Vec<Result<Self, AdifError>> {
+        adif::parse(input, charset)
+            .into_iter()
+            .map(|record| record.map(Self::from_fields))
+            .collect()
+    }
+
+    /// Parse every record of an ADX (XML ADIF) document
+    /// Malformed records are returned as errors in their place
+    pub fn from_adx(input: &[u8], charset: &'static Encoding) -> Vec<Result<Self, AdifError>> {
+        adx::parse(&adx::decode(input, charset))
+            .into_iter()
+            .map(|record| record.map(Self::from_fields))
+            .collect()
+    }
+
+    /// Parse an ADI or ADX log, telling them apart by their content
+    pub fn from_log(input: &[u8], charset: &'static Encoding) -> Vec<Result<Self, AdifError>> {
+        if adx::is_adx(input) {
+            Self::from_adx(input, charset)
+        } else {
+            Self::from_adif(input, charset)
+        }
+    }
+
+    /// Build a QSO from the fields of one record
+    /// ADIF 3.1 `_INTL` fields are kept as received; their ASCII-only plain
+    /// counterparts are not filled in from them
+    fn from_fields(fields: Vec<Field>) -> Self {
+        let map: HashMap<String, String> = fields.iter()
+            .map(|field| (field.name.to_lowercase(), field.value.clone()))
+            .collect();
+        Self { fields, ..Self::from_map(&map) }
+    }
+
+    /// Fill in `band` and `band_rx` from `freq` and `freq_rx` (in MHz) where the logger left them out
+    pub fn fill_bands(&mut self) {
+        let band_of = |freq: &str| freq.trim().parse::<f64>().ok().and_then(band_from_mhz);
+        if self.band.is_empty()
+            && let Some(band) = band_of(&self.freq) {
+            self.band = band.to_string();
+        }
+        if self.band_rx.is_empty()
+            && let Some(band) = band_of(&self.freq_rx) {
+            self.band_rx = band.to_string();
+        }
+    }
+
+    /// Replace a submode sent as MODE (e.g. `FT4`, `USB`, `PSK31`) by its ADIF MODE / SUBMODE pair
+    /// Mappings from the settings are tried before the built-in ADIF table
+    pub fn normalize_mode(&mut self, mappings: &[ModeMapping]) {
+        let mode = self.mode.trim().to_uppercase();
+        if mode.is_empty() {
+            return;
+        }
+        if let Some(mapping) = mappings.iter().find(|mapping| mapping.from.trim().eq_ignore_ascii_case(&mode)) {
+            self.mode = mapping.mode.trim().to_uppercase();
+            if let Some(submode) = &mapping.submode {
+                self.submode = submode.trim().to_uppercase();
             }
-            if field == "EOH" {
-                current.clear();
-                continue;
+            return;
+        }
+        match SUBMODES.iter().find(|(submode, _)| *submode == mode) {
+            Some((submode, parent)) => {
+                self.mode = parent.to_string();
+                if self.submode.is_empty() {
+                    self.submode = submode.to_string();
+                }
             }
-            current.insert(field.to_lowercase(), value);
+            None => self.mode = mode,
         }
-        qsos
     }
-    
+
+    /// Add a field without a typed counterpart, e.g. `NAME` or `APP_N1MM_POINTS`
+    /// Empty values are ignored
+    pub fn push_field(&mut self, name: &str, value: impl Into<String>) {
+        let value = value.into();
+        if !value.is_empty() {
+            self.fields.push(Field { name: name.to_uppercase(), data_type: None, value });
+        }
+    }
+
+    /// Typed fields with their ADIF names
+    fn typed_fields(&self) -> [(&'static str, &str); 21] {
+        [
+            ("CALL", &self.call),
+            ("GRIDSQUARE", &self.gridsquare),
+            ("MODE", &self.mode),
+            ("SUBMODE", &self.submode),
+            ("RST_SENT", &self.rst_sent),
+            ("RST_RCVD", &self.rst_rcvd),
+            ("QSO_DATE", &self.qso_date),
+            ("TIME_ON", &self.time_on),
+            ("QSO_DATE_OFF", &self.qso_date_off),
+            ("TIME_OFF", &self.time_off),
+            ("BAND", &self.band),
+            ("BAND_RX", &self.band_rx),
+            ("FREQ", &self.freq),
+            ("FREQ_RX", &self.freq_rx),
+            ("OPERATOR", &self.operator),
+            ("COMMENT", &self.comment),
+            ("POWER", &self.power),
+            ("MY_GRIDSQUARE", &self.my_gridsquare),
+            ("STATION_CALLSIGN", &self.station_callsign),
+            ("CONTEST_ID", &self.contest_id),
+            ("SRX_STRING", &self.srx_string),
+        ]
+    }
+
+    /// Every non-empty field with its data type and value, as uploaded
+    /// Received fields keep their order and data type, with the value of the typed
+    /// field if there is one; typed fields that were not received follow
+    pub fn adif_fields(&self) -> Vec<(&str, Option<&str>, &str)> {
+        let typed = self.typed_fields();
+        let received = self.fields.iter().map(|field| {
+            let value = typed.iter()
+                .find(|(name, _)| *name == field.name)
+                .map_or(field.value.as_str(), |(_, value)| value);
+            (field.name.as_str(), field.data_type.as_deref(), value)
+        });
+        let unreceived = typed.into_iter()
+            .filter(|(name, _)| !self.fields.iter().any(|field| field.name == *name))
+            .map(|(name, value)| (name, None, value));
+        received.chain(unreceived)
+            .filter(|(_, _, value)| !value.is_empty())
+            .collect()
+    }
+
+    /// Build an ADIF record with every field
+    /// Lengths count the UTF-8 bytes of each value, so `_INTL` and other non-ASCII values stay intact
     pub fn to_adif(&self) -> String {
         let mut adif = String::new();
-        if !self.call.is_empty() { adif.push_str(&format!("<CALL:{}>{}", self.call.len(), self.call)); }
-        if !self.gridsquare.is_empty() { adif.push_str(&format!("<GRIDSQUARE:{}>{}", self.gridsquare.len(), self.gridsquare)); }
-        if !self.mode.is_empty() { adif.push_str(&format!("<MODE:{}>{}", self.mode.len(), self.mode)); }
-        if !self.submode.is_empty() { adif.push_str(&format!("<SUBMODE:{}>{}", self.submode.len(), self.submode)); }
-        if !self.rst_sent.is_empty() { adif.push_str(&format!("<RST_SENT:{}>{}", self.rst_sent.len(), self.rst_sent)); }
-        if !self.rst_rcvd.is_empty() { adif.push_str(&format!("<RST_RCVD:{}>{}", self.rst_rcvd.len(), self.rst_rcvd)); }
-        if !self.qso_date.is_empty() { adif.push_str(&format!("<QSO_DATE:{}>{}", self.qso_date.len(), self.qso_date)); }
-        if !self.time_on.is_empty() { adif.push_str(&format!("<TIME_ON:{}>{}", self.time_on.len(), self.time_on)); }
-        if !self.qso_date_off.is_empty() { adif.push_str(&format!("<QSO_DATE_OFF:{}>{}", self.qso_date_off.len(), self.qso_date_off)); }
-        if !self.time_off.is_empty() { adif.push_str(&format!("<TIME_OFF:{}>{}", self.time_off.len(), self.time_off)); }
-        if !self.band.is_empty() { adif.push_str(&format!("<BAND:{}>{}", self.band.len(), self.band)); }
-        if !self.freq.is_empty() { adif.push_str(&format!("<FREQ:{}>{}", self.freq.len(), self.freq)); }
-        if !self.freq_rx.is_empty() { adif.push_str(&format!("<FREQ_RX:{}>{}", self.freq_rx.len(), self.freq_rx)); }
-        if !self.operator.is_empty() { adif.push_str(&format!("<OPERATOR:{}>{}", self.operator.len(), self.operator)); }
-        if !self.comment.is_empty() { adif.push_str(&format!("<COMMENT:{}>{}", self.comment.len(), self.comment)); }
-        if !self.power.is_empty() { adif.push_str(&format!("<POWER:{}>{}", self.power.len(), self.power)); }
-        if !self.my_gridsquare.is_empty() { adif.push_str(&format!("<MY_GRIDSQUARE:{}>{}", self.my_gridsquare.len(), self.my_gridsquare)); }
-        if !self.station_callsign.is_empty() { adif.push_str(&format!("<STATION_CALLSIGN:{}>{}", self.station_callsign.len(), self.station_callsign)); }
+        for (name, data_type, value) in self.adif_fields() {
+            match data_type {
+                Some(data_type) => adif.push_str(&format!("<{}:{}:{}>{}", name, value.len(), data_type, value)),
+                None => adif.push_str(&format!("<{}:{}>{}", name, value.len(), value)),
+            }
+        }
         adif.push_str("<EOR>\r\n");
         adif
     }
+
+    /// Build an ADX document with every field of the given QSOs
+    #[allow(dead_code)] // The gate only imports ADX; export is for tools built on this type
+    pub fn to_adx(qsos: &[QSO]) -> String {
+        adx::write_document(qsos.iter().map(QSO::adif_fields))
+    }
+}
+
+/// ADIF submodes commonly sent as MODE, with the mode they belong to
+const SUBMODES: &[(&str, &str)] = &[
+    ("USB", "SSB"), ("LSB", "SSB"),
+    ("FT4", "MFSK"), ("FST4", "MFSK"), ("FST4W", "MFSK"), ("JS8", "MFSK"), ("Q65", "MFSK"),
+    ("JTMS", "MFSK"), ("FSQCALL", "MFSK"), ("MFSK4", "MFSK"), ("MFSK8", "MFSK"),
+    ("MFSK11", "MFSK"), ("MFSK16", "MFSK"), ("MFSK22", "MFSK"), ("MFSK31", "MFSK"),
+    ("MFSK32", "MFSK"), ("MFSK64", "MFSK"), ("MFSK128", "MFSK"),
+    ("PSK31", "PSK"), ("PSK63", "PSK"), ("PSK63F", "PSK"), ("PSK125", "PSK"), ("PSK250", "PSK"),
+    ("PSK500", "PSK"), ("PSK1000", "PSK"), ("PSK10", "PSK"), ("PSKAM10", "PSK"),
+    ("PSKAM31", "PSK"), ("PSKAM50", "PSK"), ("PSKFEC31", "PSK"), ("PSK2K", "PSK"),
+    ("BPSK31", "PSK"), ("BPSK63", "PSK"), ("BPSK125", "PSK"), ("QPSK31", "PSK"),
+    ("QPSK63", "PSK"), ("QPSK125", "PSK"), ("QPSK250", "PSK"), ("QPSK500", "PSK"),
+    ("8PSK125", "PSK"), ("8PSK250", "PSK"), ("8PSK500", "PSK"), ("8PSK1000", "PSK"),
+    ("FSK31", "PSK"), ("SIM31", "PSK"),
+    ("ASCI", "RTTY"), ("PCW", "CW"),
+    ("DOMINOEX", "DOMINO"), ("DOMINOF", "DOMINO"),
+    ("FMHELL", "HELL"), ("FSKHELL", "HELL"), ("HELL80", "HELL"), ("HELLX5", "HELL"),
+    ("HELLX9", "HELL"), ("HFSK", "HELL"), ("PSKHELL", "HELL"), ("SLOWHELL", "HELL"),
+    ("JT65A", "JT65"), ("JT65B", "JT65"), ("JT65B2", "JT65"), ("JT65C", "JT65"), ("JT65C2", "JT65"),
+    ("JT4A", "JT4"), ("JT4B", "JT4"), ("JT4C", "JT4"), ("JT4D", "JT4"), ("JT4E", "JT4"),
+    ("JT4F", "JT4"), ("JT4G", "JT4"),
+    ("ISCAT-A", "ISCAT"), ("ISCAT-B", "ISCAT"),
+    ("C4FM", "DIGITALVOICE"), ("DMR", "DIGITALVOICE"), ("DSTAR", "DIGITALVOICE"),
+    ("FREEDV", "DIGITALVOICE"), ("M17", "DIGITALVOICE"),
+    ("VARA HF", "DYNAMIC"), ("VARA SATELLITE", "DYNAMIC"), ("VARA FM 1200", "DYNAMIC"),
+    ("VARA FM 9600", "DYNAMIC"),
+    ("PAC2", "PAC"), ("PAC3", "PAC"), ("PAC4", "PAC"),
+    ("PAX2", "PAX"),
+    ("THOR4", "THOR"), ("THOR5", "THOR"), ("THOR8", "THOR"), ("THOR11", "THOR"),
+    ("THOR16", "THOR"), ("THOR22", "THOR"), ("THOR25X4", "THOR"), ("THOR50X1", "THOR"),
+    ("THOR50X2", "THOR"), ("THOR100", "THOR"),
+    ("THRBX", "THRB"), ("AMTORFEC", "TOR"), ("GTOR", "TOR"), ("NAVTEX", "TOR"), ("SITORB", "TOR"),
+];
+
+/// ADIF band plan: lower and upper edge in MHz and band name
+pub const BAND_PLAN: &[(f64, f64, &str)] = &[
+    (0.1357, 0.1378, "2190m"),
+    (0.472, 0.479, "630m"),
+    (0.501, 0.504, "560m"),
+    (1.8, 2.0, "160m"),
+    (3.5, 4.0, "80m"),
+    (5.06, 5.45, "60m"),
+    (7.0, 7.3, "40m"),
+    (10.1, 10.15, "30m"),
+    (14.0, 14.35, "20m"),
+    (18.068, 18.168, "17m"),
+    (21.0, 21.45, "15m"),
+    (24.890, 24.99, "12m"),
+    (28.0, 29.7, "10m"),
+    (40.0, 45.0, "8m"),
+    (50.0, 54.0, "6m"),
+    (54.000001, 69.9, "5m"),
+    (70.0, 71.0, "4m"),
+    (144.0, 148.0, "2m"),
+    (222.0, 225.0, "1.25m"),
+    (420.0, 450.0, "70cm"),
+    (902.0, 928.0, "33cm"),
+    (1240.0, 1300.0, "23cm"),
+    (2300.0, 2450.0, "13cm"),
+    (3300.0, 3500.0, "9cm"),
+    (5650.0, 5925.0, "6cm"),
+    (10000.0, 10500.0, "3cm"),
+    (24000.0, 24250.0, "1.25cm"),
+    (47000.0, 47200.0, "6mm"),
+    (75500.0, 81000.0, "4mm"),
+    (119980.0, 123000.0, "2.5mm"),
+    (134000.0, 149000.0, "2mm"),
+    (241000.0, 250000.0, "1mm"),
+    (300000.0, 7500000.0, "submm"),
+];
+
+/// Look up the ADIF band for a frequency in MHz
+pub fn band_from_mhz(mhz: f64) -> Option<&'static str> {
+    BAND_PLAN.iter()
+        .find(|(low, high, _)| (*low..=*high).contains(&mhz))
+        .map(|(_, _, band)| *band)
+}
+
+/// Look up the ADIF band for a frequency in Hz
+pub fn band_from_hz(hz: u64) -> Option<&'static str> {
+    band_from_mhz(hz as f64 / 1_000_000.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ADI: &str = "<CALL:6>DL1ABC<QSO_DATE:8:D>20240315<TIME_ON:6>123456<BAND:3>20m\
+        <FREQ:9:N>14.074000<MODE:3>FT8<RST_SENT:3>-10<COMMENT:16>a <b> & \"quoted\"\
+        <APP_N1MM_EXCHANGE1:3:S>599<APP_LOG4OM_RX_PWR:2>10<EPC:4:N>1234<MY_FIELD:5>hello<EOR>\r\n";
+
+    fn from_adif(adif: &str) -> QSO {
+        let mut qsos = QSO::from_adif(adif.as_bytes(), encoding_rs::UTF_8);
+        assert_eq!(qsos.len(), 1);
+        qsos.remove(0).unwrap()
+    }
+
+    fn from_adx(adx: &str) -> Vec<QSO> {
+        QSO::from_adx(adx.as_bytes(), encoding_rs::UTF_8).into_iter().map(Result::unwrap).collect()
+    }
+
+    #[test]
+    fn band_plan_edges() {
+        assert_eq!(band_from_mhz(0.1357), Some("2190m"));
+        assert_eq!(band_from_mhz(5.06), Some("60m"));
+        assert_eq!(band_from_mhz(5.45), Some("60m"));
+        assert_eq!(band_from_mhz(5.0599), None);
+        assert_eq!(band_from_mhz(5.4501), None);
+        assert_eq!(band_from_mhz(50.0), Some("6m"));
+        assert_eq!(band_from_mhz(54.0), Some("6m"));
+        assert_eq!(band_from_mhz(54.000001), Some("5m"));
+        assert_eq!(band_from_mhz(69.9), Some("5m"));
+        assert_eq!(band_from_mhz(69.95), None);
+        assert_eq!(band_from_mhz(15.0), None);
+        assert_eq!(band_from_hz(7_074_000), Some("40m"));
+        assert_eq!(band_from_hz(144_174_000), Some("2m"));
+
+        // Bands are sorted and do not overlap, so every frequency has at most one band
+        for bands in BAND_PLAN.windows(2) {
+            assert!(bands[0].0 < bands[0].1 && bands[0].1 < bands[1].0, "{:?}", bands);
+        }
+    }
+
+    #[test]
+    fn bands_are_filled_from_frequencies() {
+        let mut qso = from_adif("<CALL:6>DL1ABC<FREQ:6>14.074<FREQ_RX:8>7.074000<EOR>");
+        qso.fill_bands();
+        assert_eq!(qso.band, "20m");
+        assert_eq!(qso.band_rx, "40m");
+        assert_eq!(qso.to_adif(), "<CALL:6>DL1ABC<FREQ:6>14.074<FREQ_RX:8>7.074000<BAND:3>20m<BAND_RX:3>40m<EOR>\r\n");
+
+        // Bands sent by the logger are kept, and frequencies outside the band plan add nothing
+        let mut qso = from_adif("<CALL:6>DL1ABC<BAND:3>40m<FREQ:6>14.074<FREQ_RX:4>15.0<EOR>");
+        qso.fill_bands();
+        assert_eq!(qso.band, "40m");
+        assert_eq!(qso.band_rx, "");
+    }
+
+    fn normalized(mode: &str, submode: &str, mappings: &[ModeMapping]) -> (String, String) {
+        let mut qso = QSO { mode: mode.to_string(), submode: submode.to_string(), ..QSO::default() };
+        qso.normalize_mode(mappings);
+        (qso.mode, qso.submode)
+    }
+
+    fn mapping(from: &str, mode: &str, submode: Option<&str>) -> ModeMapping {
+        ModeMapping { from: from.to_string(), mode: mode.to_string(), submode: submode.map(str::to_string) }
+    }
+
+    #[test]
+    fn submodes_sent_as_mode_are_normalized() {
+        let pair = |mode: &str, submode: &str| (mode.to_string(), submode.to_string());
+        assert_eq!(normalized("FT4", "", &[]), pair("MFSK", "FT4"));
+        assert_eq!(normalized("usb", "", &[]), pair("SSB", "USB"));
+        assert_eq!(normalized("PSK31", "", &[]), pair("PSK", "PSK31"));
+        assert_eq!(normalized(" ft8 ", "", &[]), pair("FT8", ""));
+        assert_eq!(normalized("SSB", "LSB", &[]), pair("SSB", "LSB"));
+        assert_eq!(normalized("", "", &[]), pair("", ""));
+
+        // A submode sent by the logger is kept
+        assert_eq!(normalized("FT4", "FT4X", &[]), pair("MFSK", "FT4X"));
+    }
+
+    #[test]
+    fn configured_mode_mappings_come_first() {
+        let pair = |mode: &str, submode: &str| (mode.to_string(), submode.to_string());
+        let mappings = [
+            mapping("usb", "SSB", Some("USB-D")),
+            mapping("VARAC", "DYNAMIC", Some("VARA HF")),
+            mapping("DIGI", "PKT", None),
+        ];
+        assert_eq!(normalized("USB", "", &mappings), pair("SSB", "USB-D"));
+        assert_eq!(normalized("varac", "", &mappings), pair("DYNAMIC", "VARA HF"));
+        assert_eq!(normalized("DIGI", "PACTOR", &mappings), pair("PKT", "PACTOR"));
+        assert_eq!(normalized("FT4", "", &mappings), pair("MFSK", "FT4"));
+    }
+
+    #[test]
+    fn adi_to_adx_and_back_keeps_every_field() {
+        let qso = from_adif(ADI);
+        let adx = QSO::to_adx(std::slice::from_ref(&qso));
+        let back = from_adx(&adx);
+        assert_eq!(back.len(), 1);
+        assert_eq!(back[0].fields, qso.fields);
+        assert_eq!(back[0].to_adif(), qso.to_adif());
+        assert_eq!(back[0].to_adif(), ADI);
+    }
+
+    #[test]
+    fn adx_to_adi_and_back_keeps_every_field() {
+        let qsos = vec![from_adif(ADI), from_adif("<CALL:5>K1ABC<BAND:3>40m<EPC:1:N>7<EOR>\r\n")];
+        let adx = QSO::to_adx(&qsos);
+        let adi: String = from_adx(&adx).iter().map(QSO::to_adif).collect();
+        let back = from_adx(&QSO::to_adx(&QSO::from_adif(adi.as_bytes(), encoding_rs::UTF_8)
+            .into_iter()
+            .map(Result::unwrap)
+            .collect::<Vec<_>>()));
+        assert_eq!(back.len(), 2);
+        for (back, qso) in back.iter().zip(&qsos) {
+            assert_eq!(back.fields, qso.fields);
+        }
+    }
+
+    #[test]
+    fn intl_fields_are_kept_as_received() {
+        let qso = from_adif("<CALL:6>DL1ABC<NAME_INTL:7>Jürgen<QTH_INTL:6>東京<EOR>\r\n");
+        assert!(!qso.fields.iter().any(|field| field.name == "NAME" || field.name == "QTH"));
+        assert_eq!(qso.to_adif(), "<CALL:6>DL1ABC<NAME_INTL:7>Jürgen<QTH_INTL:6>東京<EOR>\r\n");
+        let back = from_adx(&QSO::to_adx(std::slice::from_ref(&qso)));
+        assert_eq!(back[0].fields, qso.fields);
+    }
+
+    #[test]
+    fn adx_writes_app_userdef_and_typed_fields() {
+        let adx = QSO::to_adx(&[from_adif(ADI)]);
+        assert!(adx.starts_with("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\r\n<ADX>\r\n<HEADER>\r\n<ADIF_VER>"));
+        assert!(adx.contains("<USERDEF FIELDID=\"1\" TYPE=\"N\">EPC</USERDEF>"));
+        assert!(adx.contains("<USERDEF FIELDID=\"2\">MY_FIELD</USERDEF>"));
+        assert!(adx.contains("</HEADER>\r\n<RECORDS>\r\n<RECORD>\r\n"));
+        assert!(adx.ends_with("</RECORD>\r\n</RECORDS>\r\n</ADX>\r\n"));
+        assert!(adx.contains("<QSO_DATE TYPE=\"D\">20240315</QSO_DATE>"));
+        assert!(adx.contains("<APP PROGRAMID=\"N1MM\" FIELDNAME=\"EXCHANGE1\" TYPE=\"S\">599</APP>"));
+        assert!(adx.contains("<APP PROGRAMID=\"LOG4OM\" FIELDNAME=\"RX_PWR\">10</APP>"));
+        assert!(adx.contains("<USERDEF FIELDNAME=\"EPC\">1234</USERDEF>"));
+        assert!(adx.contains("<COMMENT>a &lt;b&gt; &amp; &quot;quoted&quot;</COMMENT>"));
+        assert!(!adx.contains("<EPC>"));
+    }
 }
@@ -0,0 +1,126 @@
+use iced::futures::{SinkExt, Stream};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use std::time::Duration;
+
+/// Largest HTTP request head accepted
+const MAX_REQUEST_SIZE: usize = 8192;
+
+/// Local HTTP endpoint compatible with WaveLogGate's QSY server
+/// Wavelog's bandmap and DX cluster call `http://127.0.0.1:54321/{freq}/{mode}`
+#[derive(Debug, Clone)]
+pub struct QsyServer {
+    port: u16,
+}
+
+/// Events produced by a running QSY server
+#[derive(Debug, Clone)]
+pub enum QsyEvent {
+    /// Tune request: frequency in Hz and optional mode
+    Tune(u64, Option<String>),
+    /// The server could not be started
+    Error(String),
+}
+
+impl QsyServer {
+    /// Create a new QSY server instance, listening on localhost only
+    pub fn new(port: u16) -> Self {
+        Self { port }
+    }
+
+    /// Serve tune requests for the lifetime of the stream
+    pub fn run(self) -> impl Stream<Item = QsyEvent> {
+        iced::stream::channel(100, move |mut output| async move {
+            let listener = match TcpListener::bind(("127.0.0.1", self.port)).await {
+                Ok(listener) => listener,
+                Err(e) => {
+                    let error = format!("Failed to start QSY server on port {}: {}", self.port, e);
+                    let _ = output.send(QsyEvent::Error(error)).await;
+                    return;
+                }
+            };
+
+            loop {
+                let (stream, _src) = match listener.accept().await {
+                    Ok(connection) => connection,
+                    Err(e) => {
+                        eprintln!("QSY accept error: {}", e);
+                        tokio::time::sleep(Duration::from_millis(100)).await;
+                        continue;
+                    }
+                };
+                if let Some(event) = Self::handle_connection(stream).await
+                    && output.send(event).await.is_err() {
+                    // Receiver dropped, the subscription has been cancelled
+                    return;
+                }
+            }
+        })
+    }
+
+    /// Read one HTTP request, answer it and return the tune request it carried
+    async fn handle_connection(mut stream: TcpStream) -> Option<QsyEvent> {
+        let request = tokio::time::timeout(Duration::from_secs(2), read_request_head(&mut stream)).await
+            .ok()
+            .flatten()?;
+        let mut parts = request.split_whitespace();
+        let method = parts.next().unwrap_or_default();
+        let path = parts.next().unwrap_or_default();
+
+        let (status, event) = match method {
+            // CORS preflight from the Wavelog page
+            "OPTIONS" => ("204 No Content", None),
+            "GET" => match parse_path(path) {
+                Some((frequency, mode)) => ("200 OK", Some(QsyEvent::Tune(frequency, mode))),
+                None => ("400 Bad Request", None),
+            },
+            _ => ("405 Method Not Allowed", None),
+        };
+        let body = if status == "200 OK" { "OK" } else { "" };
+        let response = format!(
+            "HTTP/1.1 {}\r\nContent-Type: text/plain\r\nAccess-Control-Allow-Origin: *\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            status, body.len(), body
+        );
+        let _ = stream.write_all(response.as_bytes()).await;
+        event
+    }
+}
+
+/// Read the request line and headers
+async fn read_request_head(stream: &mut TcpStream) -> Option<String> {
+    let mut data = Vec::new();
+    let mut buf = [0; 1024];
+    while !data.windows(4).any(|window| window == b"\r\n\r\n") {
+        let len = stream.read(&mut buf).await.ok()?;
+        if len == 0 || data.len() + len > MAX_REQUEST_SIZE {
+            break;
+        }
+        data.extend_from_slice(&buf[..len]);
+    }
+    let head = String::from_utf8_lossy(&data).into_owned();
+    head.lines().next().map(str::to_string)
+}
+
+/// Parse `/{freq}/{mode}`; the frequency is in Hz, like WaveLogGate expects
+fn parse_path(path: &str) -> Option<(u64, Option<String>)> {
+    let mut segments = path.trim_matches('/').split('/');
+    let frequency: f64 = segments.next()?.parse().ok()?;
+    let mode = segments.next()
+        .filter(|mode| !mode.is_empty())
+        .map(str::to_string);
+    (frequency > 0.0).then_some((frequency.round() as u64, mode))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frequencies_are_in_hz() {
+        assert_eq!(parse_path("/14074000/USB"), Some((14_074_000, Some("USB".to_string()))));
+        assert_eq!(parse_path("/136000/CW"), Some((136_000, Some("CW".to_string()))));
+        assert_eq!(parse_path("/475500/"), Some((475_500, None)));
+        assert_eq!(parse_path("/0/CW"), None);
+        assert_eq!(parse_path("/abc/CW"), None);
+    }
+}
@@ -0,0 +1,192 @@
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+use std::time::Duration;
+
+/// Timeout for a single rigctld command
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Client for the Hamlib rigctld TCP protocol
+#[derive(Debug)]
+pub struct Rigctld {
+    stream: BufReader<TcpStream>,
+}
+
+impl Rigctld {
+    /// Connect to rigctld
+    pub async fn connect(host: &str, port: u16) -> Result<Self, RigctldError> {
+        let stream = tokio::time::timeout(COMMAND_TIMEOUT, TcpStream::connect((host, port))).await
+            .map_err(|_| RigctldError::Timeout)?
+            .map_err(|e| RigctldError::ConnectError(format!("{}:{}", host, port), e))?;
+        Ok(Self { stream: BufReader::new(stream) })
+    }
+
+    /// Get the VFO frequency in Hz
+    pub async fn get_frequency(&mut self) -> Result<u64, RigctldError> {
+        let value = first_value(self.command("f", 1).await?)?;
+        value.parse::<f64>()
+            .map(|freq| freq as u64)
+            .map_err(|_| RigctldError::BadResponse(value))
+    }
+
+    /// Get the mode (e.g. `USB`, `CW`, `PKTUSB`)
+    pub async fn get_mode(&mut self) -> Result<String, RigctldError> {
+        // The second line is the passband width
+        first_value(self.command("m", 2).await?)
+    }
+
+    /// Get the RF power in watts for the given frequency and mode
+    pub async fn get_power(&mut self, frequency: u64, mode: &str) -> Result<f64, RigctldError> {
+        let level = first_value(self.command("l RFPOWER", 1).await?)?;
+        let value = first_value(self.command(&format!("\\power2mW {} {} {}", level, frequency, mode), 1).await?)?;
+        value.parse::<f64>()
+            .map(|mw| (mw / 1000.0).round())
+            .map_err(|_| RigctldError::BadResponse(value))
+    }
+
+    /// Tune the VFO to a frequency in Hz
+    pub async fn set_frequency(&mut self, frequency: u64) -> Result<(), RigctldError> {
+        self.command(&format!("F {}", frequency), 0).await.map(|_| ())
+    }
+
+    /// Set the mode, keeping the radio's default passband
+    pub async fn set_mode(&mut self, mode: &str) -> Result<(), RigctldError> {
+        self.command(&format!("M {} 0", mode), 0).await.map(|_| ())
+    }
+
+    /// Send a command and read its response
+    /// Get commands answer with `lines` values, set commands with `RPRT 0`
+    async fn command(&mut self, command: &str, lines: usize) -> Result<Vec<String>, RigctldError> {
+        tokio::time::timeout(COMMAND_TIMEOUT, async {
+            self.stream.get_mut().write_all(format!("{}\n", command).as_bytes()).await?;
+
+            let mut response = Vec::new();
+            loop {
+                let mut line = String::new();
+                if self.stream.read_line(&mut line).await? == 0 {
+                    return Err(RigctldError::Closed);
+                }
+                let line = line.trim().to_string();
+                if let Some(code) = line.strip_prefix("RPRT ") {
+                    return match code.trim() {
+                        "0" => Ok(response),
+                        code => Err(RigctldError::Rejected(command.to_string(), code.to_string())),
+                    };
+                }
+                response.push(line);
+                if lines > 0 && response.len() == lines {
+                    return Ok(response);
+                }
+            }
+        })
+        .await
+        .map_err(|_| RigctldError::Timeout)?
+    }
+}
+
+/// First value of a get command's response
+/// Some backends answer a get command they do not support with a bare `RPRT 0`
+fn first_value(lines: Vec<String>) -> Result<String, RigctldError> {
+    lines.into_iter().next().ok_or_else(|| RigctldError::BadResponse("RPRT 0".to_string()))
+}
+
+/// rigctld client error types
+#[derive(Debug)]
+pub enum RigctldError {
+    ConnectError(String, std::io::Error),
+    IoError(std::io::Error),
+    Timeout,
+    Closed,
+    Rejected(String, String),
+    BadResponse(String),
+}
+
+impl From<std::io::Error> for RigctldError {
+    fn from(err: std::io::Error) -> Self {
+        RigctldError::IoError(err)
+    }
+}
+
+impl std::fmt::Display for RigctldError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RigctldError::ConnectError(addr, err) => {
+                write!(f, "Failed to connect to rigctld at {}: {}", addr, err)
+            }
+            RigctldError::IoError(err) => write!(f, "rigctld error: {}", err),
+            RigctldError::Timeout => write!(f, "rigctld did not respond"),
+            RigctldError::Closed => write!(f, "rigctld closed the connection"),
+            RigctldError::Rejected(command, code) => {
+                write!(f, "rigctld rejected '{}' (RPRT {})", command, code)
+            }
+            RigctldError::BadResponse(response) => {
+                write!(f, "Unexpected rigctld response '{}'", response)
+            }
+        }
+    }
+}
+
+impl std::error::Error for RigctldError {}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+
+    /// Serve one rigctld connection on a local port, answering each command with `respond`
+    /// The connection is closed when `respond` has no answer
+    pub async fn stand_in(respond: fn(&str) -> Option<&'static str>) -> u16 {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut stream = BufReader::new(stream);
+            let mut line = String::new();
+            while stream.read_line(&mut line).await.unwrap_or(0) > 0 {
+                let Some(response) = respond(line.trim()) else {
+                    return;
+                };
+                if stream.get_mut().write_all(response.as_bytes()).await.is_err() {
+                    return;
+                }
+                line.clear();
+            }
+        });
+        port
+    }
+
+    fn rig(command: &str) -> Option<&'static str> {
+        match command {
+            "f" => Some("14074000\n"),
+            "m" => Some("PKTUSB\n3000\n"),
+            "l RFPOWER" => Some("0.5\n"),
+            "\\power2mW 0.5 14074000 PKTUSB" => Some("50000\n"),
+            "F 7074000" => Some("RPRT 0\n"),
+            _ => Some("RPRT -11\n"),
+        }
+    }
+
+    #[tokio::test]
+    async fn reads_frequency_mode_and_power() {
+        let mut rig = Rigctld::connect("127.0.0.1", stand_in(rig).await).await.unwrap();
+        assert_eq!(rig.get_frequency().await.unwrap(), 14_074_000);
+        assert_eq!(rig.get_mode().await.unwrap(), "PKTUSB");
+        assert_eq!(rig.get_power(14_074_000, "PKTUSB").await.unwrap(), 50.0);
+        assert!(rig.set_frequency(7_074_000).await.is_ok());
+        assert!(matches!(rig.set_mode("DIGI").await, Err(RigctldError::Rejected(_, code)) if code == "-11"));
+        // The connection stays usable after a rejected command
+        assert_eq!(rig.get_frequency().await.unwrap(), 14_074_000);
+    }
+
+    #[tokio::test]
+    async fn missing_values_are_bad_responses() {
+        let port = stand_in(|command| match command {
+            "f" | "m" => Some("RPRT 0\n"),
+            _ => None,
+        }).await;
+        let mut rig = Rigctld::connect("127.0.0.1", port).await.unwrap();
+        assert!(matches!(rig.get_frequency().await, Err(RigctldError::BadResponse(_))));
+        assert!(matches!(rig.get_mode().await, Err(RigctldError::BadResponse(_))));
+        assert!(matches!(rig.get_frequency().await, Err(RigctldError::BadResponse(_))));
+        assert!(matches!(rig.get_power(14_074_000, "USB").await, Err(RigctldError::Closed)));
+    }
+}
@@ -1,10 +1,27 @@
 use serde::Deserialize;
 use config::Config;
+use encoding_rs::Encoding;
 
 #[derive(Debug, Clone, Deserialize)]
 pub struct Settings {
     pub wavelog: WaveLogSettings,
+    #[serde(default)]
     pub server: ServerSettings,
+    /// Listeners configured with `[[listener]]`; when empty, `[server]` is used
+    #[serde(default, rename = "listener")]
+    pub listeners: Vec<ListenerSettings>,
+    /// ADIF log files followed with `[[tail]]`
+    #[serde(default, rename = "tail")]
+    pub tails: Vec<TailSettings>,
+    /// Drop folder for batch import of ADIF files
+    pub inbox: Option<InboxSettings>,
+    /// CAT control of the radio
+    pub cat: Option<CatSettings>,
+    #[serde(default)]
+    pub upload: UploadSettings,
+    /// Extra mode mappings configured with `[[mode_map]]`, applied before the built-in ones
+    #[serde(default, rename = "mode_map")]
+    pub mode_map: Vec<ModeMapping>,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -20,10 +37,221 @@ pub struct ServerSettings {
     pub host: String,
     #[serde(default = "default_port")]
     pub port: u16,
+    /// Optional TCP port for loggers that stream ADIF over TCP
+    pub tcp_port: Option<u16>,
+    /// Multicast group to join on the UDP port
+    pub multicast: Option<MulticastSettings>,
+    /// Accept IPv4 traffic when `host` is an IPv6 address such as `::`
+    #[serde(default)]
+    pub dual_stack: bool,
+    /// Networks (CIDR) allowed to send log packets; all sources when empty
+    #[serde(default)]
+    pub allow: Vec<String>,
+    /// Networks (CIDR) whose packets are always rejected
+    #[serde(default)]
+    pub deny: Vec<String>,
+    /// Downstream `host:port` targets that receive a copy of every UDP datagram
+    #[serde(default)]
+    pub forward: Vec<String>,
+    /// Local port of the WaveLogGate compatible WebSocket event feed, 0 to disable
+    #[serde(default = "default_ws_port")]
+    pub ws_port: u16,
+    /// Charset of received ADIF and XML that is not valid UTF-8
+    #[serde(default = "default_charset", deserialize_with = "crate::charset::deserialize")]
+    pub charset: &'static Encoding,
+}
+
+/// UDP multicast group membership
+#[derive(Debug, Clone, Hash, Deserialize)]
+pub struct MulticastSettings {
+    /// Group address, e.g. `224.0.0.1` or `239.255.0.1`
+    pub group: String,
+    /// Local interface address (IPv4) or index (IPv6); the OS default when unset
+    pub interface: Option<String>,
+    pub ttl: Option<u32>,
+}
+
+impl Default for ServerSettings {
+    fn default() -> Self {
+        Self {
+            host: default_host(),
+            port: default_port(),
+            tcp_port: None,
+            multicast: None,
+            dual_stack: false,
+            allow: Vec::new(),
+            deny: Vec::new(),
+            forward: Vec::new(),
+            ws_port: default_ws_port(),
+            charset: default_charset(),
+        }
+    }
+}
+
+/// Limits protecting Wavelog from floods of QSOs
+#[derive(Debug, Clone, Deserialize)]
+pub struct UploadSettings {
+    /// Maximum number of QSOs uploaded at the same time
+    #[serde(default = "default_concurrency")]
+    pub concurrency: usize,
+    /// QSOs per second uploaded from a single network source, 0 to disable
+    /// Further QSOs from that source wait in the queue
+    #[serde(default = "default_rate")]
+    pub rate: f64,
+    /// QSOs a network source may send at once before `rate` applies
+    #[serde(default = "default_burst")]
+    pub burst: u32,
+    /// Maximum number of network QSOs waiting for upload; further ones are dropped
+    /// and listed in the table. QSOs from tailed files are never dropped
+    #[serde(default = "default_max_queue")]
+    pub max_queue: usize,
+}
+
+impl Default for UploadSettings {
+    fn default() -> Self {
+        Self {
+            concurrency: default_concurrency(),
+            rate: default_rate(),
+            burst: default_burst(),
+            max_queue: default_max_queue(),
+        }
+    }
+}
+
+/// Maps a mode sent by a logger to an ADIF MODE / SUBMODE pair
+#[derive(Debug, Clone, Deserialize)]
+pub struct ModeMapping {
+    /// Mode as sent by the logger, compared case-insensitively
+    pub from: String,
+    pub mode: String,
+    pub submode: Option<String>,
+}
+
+/// A single ingest listener with its own port, protocol and station profile
+#[derive(Debug, Clone, Hash, Deserialize)]
+pub struct ListenerSettings {
+    #[serde(default = "default_host")]
+    pub host: String,
+    pub port: u16,
+    #[serde(default)]
+    pub protocol: ListenerProtocol,
+    /// Wavelog station profile ID overriding `wavelog.station`
+    pub station: Option<String>,
+    /// Multicast group to join (UDP protocols only)
+    pub multicast: Option<MulticastSettings>,
+    /// Accept IPv4 traffic when `host` is an IPv6 address such as `::`
+    #[serde(default)]
+    pub dual_stack: bool,
+    /// Downstream `host:port` targets that receive a copy of every datagram (UDP protocols only)
+    #[serde(default)]
+    pub forward: Vec<String>,
+}
+
+/// An ADIF log file followed for newly appended QSOs
+#[derive(Debug, Clone, Hash, Deserialize)]
+pub struct TailSettings {
+    pub path: String,
+    /// Read the whole file on first start instead of only new records
+    #[serde(default)]
+    pub from_start: bool,
+    /// Wavelog station profile ID overriding `wavelog.station`
+    pub station: Option<String>,
+}
+
+/// A watched directory whose ADIF files are imported and then moved away
+#[derive(Debug, Clone, Hash, Deserialize)]
+pub struct InboxSettings {
+    pub path: String,
+    /// Wavelog station profile ID overriding `wavelog.station`
+    pub station: Option<String>,
+}
+
+/// CAT backend connection, polled to keep Wavelog's radio status in sync
+#[derive(Debug, Clone, Hash, Deserialize)]
+pub struct CatSettings {
+    #[serde(default)]
+    pub backend: CatBackend,
+    #[serde(default = "default_cat_host")]
+    pub host: String,
+    /// Backend port, 4532 (rigctld) or 12345 (FLRig) when unset
+    pub port: Option<u16>,
+    /// Poll interval in milliseconds
+    #[serde(default = "default_poll_interval")]
+    pub poll_interval: u64,
+    /// Radio name shown in Wavelog
+    #[serde(default = "default_radio_name")]
+    pub radio: String,
+    /// Local port of the WaveLogGate compatible QSY endpoint, 0 to disable
+    #[serde(default = "default_qsy_port")]
+    pub qsy_port: u16,
+}
+
+impl CatSettings {
+    /// Port to connect to, falling back to the backend default
+    pub fn port(&self) -> u16 {
+        self.port.unwrap_or(match self.backend {
+            CatBackend::Rigctld => 4532,
+            CatBackend::Flrig => 12345,
+        })
+    }
+}
+
+/// Program used to talk to the radio
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CatBackend {
+    /// Hamlib rigctld, TCP
+    #[default]
+    Rigctld,
+    /// FLRig, XML-RPC over HTTP
+    Flrig,
+}
+
+/// Protocol spoken by the program sending to a listener
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ListenerProtocol {
+    /// UDP, detecting WSJT-X binary, N1MM XML or ADIF text per datagram
+    #[default]
+    Auto,
+    /// UDP, ADIF text
+    Adif,
+    /// UDP, WSJT-X / JTDX / MSHV binary protocol
+    Wsjtx,
+    /// UDP, N1MM Logger+ XML broadcasts
+    N1mm,
+    /// UDP, JS8Call JSON API
+    Js8call,
+    /// TCP, ADIF stream
+    Tcp,
+}
+
+impl std::fmt::Display for ListenerProtocol {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            ListenerProtocol::Auto => "auto",
+            ListenerProtocol::Adif => "adif",
+            ListenerProtocol::Wsjtx => "wsjtx",
+            ListenerProtocol::N1mm => "n1mm",
+            ListenerProtocol::Js8call => "js8call",
+            ListenerProtocol::Tcp => "tcp",
+        };
+        write!(f, "{}", name)
+    }
 }
 
 fn default_host() -> String { String::from("0.0.0.0") }
 fn default_port() -> u16 { 2333 }
+fn default_cat_host() -> String { String::from("127.0.0.1") }
+fn default_poll_interval() -> u64 { 1000 }
+fn default_radio_name() -> String { String::from("rs-wavelog-gate") }
+fn default_qsy_port() -> u16 { 54321 }
+fn default_ws_port() -> u16 { 54322 }
+fn default_charset() -> &'static Encoding { encoding_rs::WINDOWS_1252 }
+fn default_concurrency() -> usize { 4 }
+fn default_rate() -> f64 { 5.0 }
+fn default_burst() -> u32 { 50 }
+fn default_max_queue() -> usize { 1000 }
 
 impl Settings {
     pub fn load() -> Result<Self, config::ConfigError> {
@@ -32,4 +260,42 @@ impl Settings {
             .build()?
             .try_deserialize::<Settings>()
     }
+
+    /// Listeners to run: the `[[listener]]` entries, or the `[server]` section if there are none
+    pub fn effective_listeners(&self) -> Vec<ListenerSettings> {
+        if !self.listeners.is_empty() {
+            return self.listeners.clone();
+        }
+
+        let mut listeners = vec![ListenerSettings {
+            host: self.server.host.clone(),
+            port: self.server.port,
+            protocol: ListenerProtocol::Auto,
+            station: None,
+            multicast: self.server.multicast.clone(),
+            dual_stack: self.server.dual_stack,
+            forward: self.server.forward.clone(),
+        }];
+        if let Some(tcp_port) = self.server.tcp_port {
+            listeners.push(ListenerSettings {
+                host: self.server.host.clone(),
+                port: tcp_port,
+                protocol: ListenerProtocol::Tcp,
+                station: None,
+                multicast: None,
+                dual_stack: self.server.dual_stack,
+                forward: Vec::new(),
+            });
+        }
+        listeners
+    }
+
+    /// Settings to use for QSOs from a source with an optional station profile override
+    pub fn for_station(&self, station: Option<&String>) -> Settings {
+        let mut settings = self.clone();
+        if let Some(station) = station {
+            settings.wavelog.station = station.clone();
+        }
+        settings
+    }
 }
\ No newline at end of file
@@ -0,0 +1,133 @@
+use crate::adif_buffer;
+
+use iced::futures::{SinkExt, Stream};
+use std::collections::HashMap;
+use std::io::SeekFrom;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+
+/// File storing the read offset of every tailed log file
+const STATE_FILE: &str = "tail_state.json";
+
+/// Interval between checks for new data
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Serializes access to the state file shared by all tailed files
+static STATE_LOCK: Mutex<()> = Mutex::new(());
+
+/// Follows an ADIF log file (e.g. `wsjtx_log.adi`) and reports appended records
+#[derive(Debug, Clone)]
+pub struct FileTail {
+    path: PathBuf,
+    from_start: bool,
+}
+
+/// Events produced by a tailed log file
+#[derive(Debug, Clone)]
+pub enum TailEvent {
+    /// Complete ADIF records appended to the file, and the offset just past them
+    /// The offset is stored with `store_offset` once the records have been uploaded
+    Records(Vec<u8>, u64),
+    /// The file could not be read
+    Error(String),
+}
+
+impl FileTail {
+    /// Create a new file tail instance
+    /// Without a stored offset, the file is read from the start only if `from_start` is set
+    pub fn new(path: PathBuf, from_start: bool) -> Self {
+        Self { path, from_start }
+    }
+
+    /// Poll the file for appended records for the lifetime of the stream
+    /// Reading continues after the last complete record; the stored offset from
+    /// which the next start reads is only advanced by the receiver
+    pub fn run(self) -> impl Stream<Item = TailEvent> {
+        iced::stream::channel(100, move |mut output| async move {
+            let key = self.path.to_string_lossy().into_owned();
+            let mut offset = match load_offset(&key) {
+                Some(offset) => offset,
+                None if self.from_start => 0,
+                None => tokio::fs::metadata(&self.path).await.map(|m| m.len()).unwrap_or(0),
+            };
+            let mut reported_error = false;
+
+            loop {
+                match self.read_records(offset).await {
+                    Ok((new_offset, records)) => {
+                        reported_error = false;
+                        if let Some(records) = records
+                            && output.send(TailEvent::Records(records, new_offset)).await.is_err() {
+                            // Receiver dropped, the subscription has been cancelled
+                            return;
+                        }
+                        offset = new_offset;
+                    }
+                    Err(e) => {
+                        // Report a missing or unreadable file once, then keep polling
+                        if !reported_error {
+                            reported_error = true;
+                            let error = format!("Failed to read {}: {}", self.path.display(), e);
+                            let _ = output.send(TailEvent::Error(error)).await;
+                        }
+                    }
+                }
+                tokio::time::sleep(POLL_INTERVAL).await;
+            }
+        })
+    }
+
+    /// Read the complete records appended after `offset`
+    /// Returns the offset just past the last complete record
+    async fn read_records(&self, offset: u64) -> std::io::Result<(u64, Option<Vec<u8>>)> {
+        let mut file = tokio::fs::File::open(&self.path).await?;
+        let len = file.metadata().await?.len();
+
+        // The file has been truncated or replaced, start over
+        let offset = if len < offset { 0 } else { offset };
+        if len == offset {
+            return Ok((offset, None));
+        }
+
+        file.seek(SeekFrom::Start(offset)).await?;
+        let mut data = Vec::new();
+        file.take(len - offset).read_to_end(&mut data).await?;
+
+        match adif_buffer::rfind_record_end(&data) {
+            Some(end) => {
+                data.truncate(end);
+                Ok((offset + end as u64, Some(data)))
+            }
+            None => Ok((offset, None)),
+        }
+    }
+}
+
+/// Load the stored offset of a tailed file
+fn load_offset(key: &str) -> Option<u64> {
+    let _lock = STATE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    read_state().get(key).copied()
+}
+
+/// Store the offset from which a tailed file is read on the next start
+pub fn store_offset(key: &str, offset: u64) {
+    let _lock = STATE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let mut state = read_state();
+    state.insert(key.to_string(), offset);
+    let result = serde_json::to_string_pretty(&state)
+        .map_err(std::io::Error::from)
+        .and_then(|json| std::fs::write(STATE_FILE, json));
+    if let Err(e) = result {
+        eprintln!("Failed to save {}: {}", STATE_FILE, e);
+    }
+}
+
+/// Read the offsets of all tailed files
+fn read_state() -> HashMap<String, u64> {
+    std::fs::read_to_string(STATE_FILE)
+        .ok()
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default()
+}
@@ -0,0 +1,155 @@
+use crate::addr;
+use crate::adif_buffer::AdifBuffer;
+use crate::filter::SourceFilter;
+
+use iced::futures::channel::mpsc;
+use iced::futures::{SinkExt, Stream};
+use tokio::io::AsyncReadExt;
+use socket2::{Protocol, Type};
+use tokio::net::{TcpListener, TcpStream};
+use std::net::SocketAddr;
+use std::time::Duration;
+
+/// TCP listener for receiving ADIF streams from Ham Radio software
+#[derive(Debug, Clone)]
+pub struct TcpAdifListener {
+    host: String,
+    port: u16,
+    dual_stack: bool,
+    filter: SourceFilter,
+}
+
+/// Events produced by a running TCP listener
+#[derive(Debug, Clone)]
+pub enum TcpEvent {
+    /// One or more complete ADIF records (terminated by `<EOR>`) have been received from the given peer
+    Record(SocketAddr, Vec<u8>),
+    /// A connection from a source rejected by the source filter has been closed
+    Rejected(SocketAddr),
+    /// The listener could not be started, or a connection sent unusable data
+    Error(String),
+}
+
+impl TcpAdifListener {
+    /// Create a new TCP listener instance
+    pub fn new(host: String, port: u16) -> Self {
+        Self { host, port, dual_stack: false, filter: SourceFilter::default() }
+    }
+
+    /// Close connections from sources the filter rejects right after accepting them
+    pub fn with_filter(mut self, filter: SourceFilter) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    /// Accept IPv4 connections on an IPv6 address as well
+    pub fn with_dual_stack(mut self, dual_stack: bool) -> Self {
+        self.dual_stack = dual_stack;
+        self
+    }
+
+    /// Bind and listen on the configured address
+    async fn bind(&self) -> Result<TcpListener, TcpListenerError> {
+        let addr = addr::display(&self.host, self.port);
+        let sock_addr = addr::resolve(&self.host, self.port).await
+            .ok_or_else(|| TcpListenerError::InvalidAddress(addr.clone()))?;
+
+        let socket = addr::socket(sock_addr, Type::STREAM, Protocol::TCP, self.dual_stack)
+            .and_then(|socket| {
+                #[cfg(unix)]
+                socket.set_reuse_address(true)?;
+                socket.bind(&sock_addr.into())?;
+                socket.listen(128)?;
+                Ok(socket)
+            })
+            .map_err(|e| TcpListenerError::BindError(addr.clone(), e))?;
+
+        TcpListener::from_std(socket.into())
+            .map_err(|e| TcpListenerError::BindError(addr, e))
+    }
+
+    /// Start TCP server and stream every ADIF record received on any connection
+    /// Each connection is served by its own task, so several loggers can be connected at once
+    pub fn run(self) -> impl Stream<Item = TcpEvent> {
+        iced::stream::channel(100, move |mut output| async move {
+            let listener = match self.bind().await {
+                Ok(listener) => listener,
+                Err(e) => {
+                    let _ = output.send(TcpEvent::Error(e.to_string())).await;
+                    return;
+                }
+            };
+
+            loop {
+                match listener.accept().await {
+                    Ok((stream, src)) if !self.filter.is_allowed(src.ip()) => {
+                        // Nothing is read from rejected sources
+                        drop(stream);
+                        if output.send(TcpEvent::Rejected(src)).await.is_err() {
+                            return;
+                        }
+                    }
+                    Ok((stream, src)) => {
+                        tokio::spawn(Self::handle_connection(stream, src, output.clone()));
+                    }
+                    Err(e) => {
+                        // Log error but continue accepting
+                        eprintln!("TCP accept error: {}", e);
+                        // Add small delay to avoid excessive CPU usage
+                        tokio::time::sleep(Duration::from_millis(100)).await;
+                    }
+                }
+            }
+        })
+    }
+
+    /// Read a single connection until it is closed, splitting the stream on `<EOR>`
+    /// Data after the last `<EOR>` is discarded when the connection closes
+    async fn handle_connection(mut stream: TcpStream, src: SocketAddr, mut output: mpsc::Sender<TcpEvent>) {
+        let mut pending = AdifBuffer::default();
+        let mut buf = [0; 4096];
+
+        loop {
+            let len = match stream.read(&mut buf).await {
+                Ok(0) => return,
+                Ok(len) => len,
+                Err(e) => {
+                    eprintln!("TCP receive error: {}", e);
+                    return;
+                }
+            };
+
+            let event = match pending.push(&buf[..len]) {
+                Ok(Some(records)) => TcpEvent::Record(src, records),
+                Ok(None) => continue,
+                Err(e) => TcpEvent::Error(format!("{}: {}", src, e)),
+            };
+            if output.send(event).await.is_err() {
+                // Receiver dropped, the subscription has been cancelled
+                return;
+            }
+        }
+    }
+}
+
+/// TCP listener error types
+#[derive(Debug)]
+pub enum TcpListenerError {
+    InvalidAddress(String),
+    BindError(String, std::io::Error),
+}
+
+impl std::fmt::Display for TcpListenerError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TcpListenerError::InvalidAddress(addr) => {
+                write!(f, "Invalid TCP address {}", addr)
+            }
+            TcpListenerError::BindError(addr, err) => {
+                write!(f, "Failed to bind to TCP address {}: {}", addr, err)
+            }
+        }
+    }
+}
+
+impl std::error::Error for TcpListenerError {}
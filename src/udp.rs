@@ -1,59 +1,204 @@
+use crate::addr;
+use crate::filter::SourceFilter;
+use crate::settings::MulticastSettings;
+
+use iced::futures::{SinkExt, Stream};
+use socket2::{Protocol, Socket, Type};
 use tokio::net::UdpSocket;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
 use std::time::Duration;
 
 /// UDP listener for receiving ADIF data from Ham Radio software
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct UdpListener {
     host: String,
     port: u16,
+    multicast: Option<MulticastSettings>,
+    dual_stack: bool,
+    forward: Vec<String>,
+    filter: SourceFilter,
+}
+
+/// Largest possible UDP payload
+const MAX_DATAGRAM_SIZE: usize = 65536;
+
+/// Events produced by a running UDP listener
+#[derive(Debug, Clone)]
+pub enum UdpEvent {
+    /// A datagram has been received from the given source
+    Datagram(SocketAddr, Vec<u8>),
+    /// The listener could not be started
+    Error(String),
 }
 
 impl UdpListener {
     /// Create a new UDP listener instance
     pub fn new(host: String, port: u16) -> Self {
-        Self { host, port }
+        Self { host, port, multicast: None, dual_stack: false, forward: Vec::new(), filter: SourceFilter::default() }
+    }
+
+    /// Relay every datagram byte-for-byte to the given `host:port` targets
+    /// Datagrams from sources rejected by the filter are not relayed
+    pub fn with_forward(mut self, forward: Vec<String>, filter: SourceFilter) -> Self {
+        self.forward = forward;
+        self.filter = filter;
+        self
+    }
+
+    /// Accept IPv4 traffic on an IPv6 address as well
+    pub fn with_dual_stack(mut self, dual_stack: bool) -> Self {
+        self.dual_stack = dual_stack;
+        self
+    }
+
+    /// Join a multicast group after binding
+    pub fn with_multicast(mut self, multicast: Option<MulticastSettings>) -> Self {
+        self.multicast = multicast;
+        self
     }
 
-    /// Start UDP server and listen for data
-    /// Returns the content of the first received data packet
-    pub async fn listen_once(&self) -> Result<Vec<u8>, UdpListenerError> {
-        let addr = format!("{}:{}", self.host, self.port);
-        
-        let sock = UdpSocket::bind(&addr).await
+    /// Bind the UDP socket for this listener
+    /// Multicast listeners share the port with other programs and join the configured group
+    pub async fn bind(&self) -> Result<UdpSocket, UdpListenerError> {
+        let addr = addr::display(&self.host, self.port);
+        let sock_addr = addr::resolve(&self.host, self.port).await
+            .ok_or_else(|| UdpListenerError::InvalidAddress(addr.clone()))?;
+
+        let socket = addr::socket(sock_addr, Type::DGRAM, Protocol::UDP, self.dual_stack)
+            .map_err(|e| UdpListenerError::BindError(addr.clone(), e))?;
+        if self.multicast.is_some() {
+            socket.set_reuse_address(true)
+                .map_err(|e| UdpListenerError::BindError(addr.clone(), e))?;
+            #[cfg(unix)]
+            socket.set_reuse_port(true)
+                .map_err(|e| UdpListenerError::BindError(addr.clone(), e))?;
+        }
+        socket.bind(&sock_addr.into())
             .map_err(|e| UdpListenerError::BindError(addr.clone(), e))?;
 
-        let mut buf = [0; 4096]; // Increase buffer size to handle larger ADIF data
-        
-        loop {
-            match sock.recv_from(&mut buf).await {
-                Ok((len, _src)) => {
-                    if len > 0 {
-                        return Ok(buf[..len].to_vec());
-                    }
+        if let Some(multicast) = &self.multicast {
+            Self::join_multicast(&socket, multicast)?;
+        }
+
+        UdpSocket::from_std(socket.into())
+            .map_err(|e| UdpListenerError::BindError(addr, e))
+    }
+
+    /// Resolve the relay targets once, skipping any that cannot be resolved
+    async fn resolve_targets(forward: &[String]) -> Vec<SocketAddr> {
+        let mut targets = Vec::new();
+        for target in forward {
+            match tokio::net::lookup_host(target.as_str()).await.ok().and_then(|mut addrs| addrs.next()) {
+                Some(addr) => targets.push(addr),
+                None => eprintln!("Invalid relay target {}", target),
+            }
+        }
+        targets
+    }
+
+    /// Send a datagram to every relay target except the one it came from
+    async fn relay(sock: &UdpSocket, targets: &[SocketAddr], src: SocketAddr, data: &[u8]) {
+        for target in targets.iter().filter(|target| **target != src) {
+            if let Err(e) = sock.send_to(data, target).await {
+                eprintln!("UDP relay to {} failed: {}", target, e);
+            }
+        }
+    }
+
+    /// Join the configured multicast group and apply the TTL
+    fn join_multicast(socket: &Socket, multicast: &MulticastSettings) -> Result<(), UdpListenerError> {
+        let group: IpAddr = multicast.group.parse()
+            .map_err(|_| UdpListenerError::InvalidAddress(multicast.group.clone()))?;
+        let interface = multicast.interface.as_deref().unwrap_or_default();
+        let join_error = |e| UdpListenerError::MulticastFailed(multicast.group.clone(), e);
+
+        match group {
+            IpAddr::V4(group) => {
+                let interface = match interface {
+                    "" => Ipv4Addr::UNSPECIFIED,
+                    interface => interface.parse()
+                        .map_err(|_| UdpListenerError::InvalidAddress(interface.to_string()))?,
+                };
+                socket.join_multicast_v4(&group, &interface).map_err(join_error)?;
+                if let Some(ttl) = multicast.ttl {
+                    socket.set_multicast_ttl_v4(ttl).map_err(join_error)?;
                 }
-                Err(e) => {
-                    // Log error but continue listening
-                    eprintln!("UDP receive error: {}", e);
-                    // Add small delay to avoid excessive CPU usage
-                    tokio::time::sleep(Duration::from_millis(100)).await;
+            }
+            IpAddr::V6(group) => {
+                // IPv6 interfaces are selected by index, 0 lets the OS choose
+                let interface = match interface {
+                    "" => 0,
+                    interface => interface.parse()
+                        .map_err(|_| UdpListenerError::InvalidAddress(interface.to_string()))?,
+                };
+                socket.join_multicast_v6(&group, interface).map_err(join_error)?;
+                if let Some(ttl) = multicast.ttl {
+                    socket.set_multicast_hops_v6(ttl).map_err(join_error)?;
                 }
             }
         }
+        Ok(())
+    }
+
+    /// Start UDP server and stream every received data packet
+    /// The socket is bound once and kept open for the lifetime of the stream,
+    /// so datagrams arriving in bursts are queued by the OS instead of lost
+    pub fn run(self) -> impl Stream<Item = UdpEvent> {
+        iced::stream::channel(100, move |mut output| async move {
+            let sock = match self.bind().await {
+                Ok(sock) => sock,
+                Err(e) => {
+                    let _ = output.send(UdpEvent::Error(e.to_string())).await;
+                    return;
+                }
+            };
+
+            let targets = Self::resolve_targets(&self.forward).await;
+            let mut buf = vec![0; MAX_DATAGRAM_SIZE]; // Large enough for any datagram, so nothing is truncated
+
+            loop {
+                match sock.recv_from(&mut buf).await {
+                    Ok((len, src)) => {
+                        if len > 0 && self.filter.is_allowed(src.ip()) {
+                            Self::relay(&sock, &targets, src, &buf[..len]).await;
+                        }
+                        if len > 0 && output.send(UdpEvent::Datagram(src, buf[..len].to_vec())).await.is_err() {
+                            // Receiver dropped, the subscription has been cancelled
+                            return;
+                        }
+                    }
+                    Err(e) => {
+                        // Log error but continue listening
+                        eprintln!("UDP receive error: {}", e);
+                        // Add small delay to avoid excessive CPU usage
+                        tokio::time::sleep(Duration::from_millis(100)).await;
+                    }
+                }
+            }
+        })
     }
 }
 
 /// UDP listener error types
 #[derive(Debug)]
 pub enum UdpListenerError {
+    InvalidAddress(String),
     BindError(String, std::io::Error),
+    MulticastFailed(String, std::io::Error),
 }
 
 impl std::fmt::Display for UdpListenerError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
+            UdpListenerError::InvalidAddress(addr) => {
+                write!(f, "Invalid address {}", addr)
+            }
             UdpListenerError::BindError(addr, err) => {
                 write!(f, "Failed to bind to address {}: {}", addr, err)
             }
+            UdpListenerError::MulticastFailed(group, err) => {
+                write!(f, "Failed to join multicast group {}: {}", group, err)
+            }
         }
     }
 }
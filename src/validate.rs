@@ -0,0 +1,360 @@
+use crate::qso::{self, QSO};
+
+/// ADIF data types checked before upload
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DataType {
+    Date,
+    Time,
+    GridSquare,
+    Number,
+    Integer,
+    Boolean,
+    /// A band of the ADIF band plan
+    Band,
+    Enumeration(&'static [&'static str]),
+}
+
+const MODES: &[&str] = &[
+    "AM", "ARDOP", "ATV", "CHIP", "CLO", "CONTESTI", "CW", "DIGITALVOICE", "DOMINO", "DYNAMIC",
+    "FAX", "FM", "FSK441", "FT8", "HELL", "ISCAT", "JT4", "JT6M", "JT9", "JT44", "JT65", "MFSK",
+    "MSK144", "MT63", "OLIVIA", "OPERA", "PAC", "PAX", "PKT", "PSK", "Q15", "QRA64", "ROS",
+    "RTTY", "RTTYM", "SSB", "SSTV", "T10", "THOR", "THRB", "TOR", "V4", "VOI", "WINMOR", "WSPR",
+];
+
+const QSL_RCVD: &[&str] = &["Y", "N", "R", "I", "V"];
+
+const QSL_SENT: &[&str] = &["Y", "N", "R", "Q", "I"];
+
+const PROP_MODES: &[&str] = &[
+    "AS", "AUE", "AUR", "BS", "ECH", "EME", "ES", "F2", "FAI", "GWAVE", "INTERNET", "ION",
+    "IRL", "LOS", "MS", "RPT", "RS", "SAT", "TEP", "TR",
+];
+
+/// Data type of the well-known ADIF fields
+const FIELD_TYPES: &[(&str, DataType)] = &[
+    ("QSO_DATE", DataType::Date),
+    ("QSO_DATE_OFF", DataType::Date),
+    ("QSLRDATE", DataType::Date),
+    ("QSLSDATE", DataType::Date),
+    ("LOTW_QSLRDATE", DataType::Date),
+    ("LOTW_QSLSDATE", DataType::Date),
+    ("EQSL_QSLRDATE", DataType::Date),
+    ("EQSL_QSLSDATE", DataType::Date),
+    ("TIME_ON", DataType::Time),
+    ("TIME_OFF", DataType::Time),
+    ("GRIDSQUARE", DataType::GridSquare),
+    ("MY_GRIDSQUARE", DataType::GridSquare),
+    ("FREQ", DataType::Number),
+    ("FREQ_RX", DataType::Number),
+    ("TX_PWR", DataType::Number),
+    ("RX_PWR", DataType::Number),
+    ("POWER", DataType::Number),
+    ("DISTANCE", DataType::Number),
+    ("ANT_AZ", DataType::Number),
+    ("ANT_EL", DataType::Number),
+    ("AGE", DataType::Number),
+    ("SFI", DataType::Integer),
+    ("K_INDEX", DataType::Integer),
+    ("A_INDEX", DataType::Integer),
+    ("CQZ", DataType::Integer),
+    ("ITUZ", DataType::Integer),
+    ("MY_CQ_ZONE", DataType::Integer),
+    ("MY_ITU_ZONE", DataType::Integer),
+    ("DXCC", DataType::Integer),
+    ("MY_DXCC", DataType::Integer),
+    ("SRX", DataType::Integer),
+    ("STX", DataType::Integer),
+    ("SWL", DataType::Boolean),
+    ("FORCE_INIT", DataType::Boolean),
+    ("BAND", DataType::Band),
+    ("BAND_RX", DataType::Band),
+    ("MODE", DataType::Enumeration(MODES)),
+    ("QSL_RCVD", DataType::Enumeration(QSL_RCVD)),
+    ("QSL_SENT", DataType::Enumeration(QSL_SENT)),
+    ("LOTW_QSL_RCVD", DataType::Enumeration(QSL_RCVD)),
+    ("LOTW_QSL_SENT", DataType::Enumeration(QSL_SENT)),
+    ("EQSL_QSL_RCVD", DataType::Enumeration(QSL_RCVD)),
+    ("EQSL_QSL_SENT", DataType::Enumeration(QSL_SENT)),
+    ("PROP_MODE", DataType::Enumeration(PROP_MODES)),
+    ("ANT_PATH", DataType::Enumeration(&["G", "O", "S", "L"])),
+    ("QSO_COMPLETE", DataType::Enumeration(&["Y", "N", "NIL", "?"])),
+];
+
+/// Fields Wavelog needs to log a QSO
+const REQUIRED_FIELDS: &[&str] = &["CALL", "QSO_DATE", "TIME_ON", "BAND"];
+
+/// Problems found in a QSO before upload
+/// Errors prevent the upload; warnings are shown with the uploaded QSO
+#[derive(Debug, Clone, Default)]
+pub struct Validation {
+    pub errors: Vec<String>,
+    pub warnings: Vec<String>,
+}
+
+/// Check a QSO against the ADIF data types
+/// Enumeration values outside the ADIF lists and frequencies outside their band
+/// are only warnings, as loggers commonly use values from older or newer ADIF
+/// versions and band plans differ between regions
+pub fn validate(qso: &QSO) -> Validation {
+    let mut validation = Validation::default();
+    let fields = qso.adif_fields();
+
+    for required in REQUIRED_FIELDS {
+        if !fields.iter().any(|(name, _, _)| name == required) {
+            validation.errors.push(format!("{} is missing", required));
+        }
+    }
+
+    for (name, declared, value) in fields {
+        let data_type = FIELD_TYPES.iter()
+            .find(|(field, _)| *field == name)
+            .map(|(_, data_type)| *data_type)
+            .or_else(|| declared.and_then(declared_type));
+        let Some(data_type) = data_type else {
+            continue;
+        };
+        match data_type {
+            DataType::Band => {
+                if !qso::BAND_PLAN.iter().any(|(_, _, band)| band.eq_ignore_ascii_case(value.trim())) {
+                    validation.warnings.push(format!("{} '{}' is not an ADIF band", name, value));
+                }
+            }
+            DataType::Enumeration(values) => {
+                if !values.iter().any(|allowed| allowed.eq_ignore_ascii_case(value.trim())) {
+                    validation.warnings.push(format!("{} '{}' is not an ADIF value", name, value));
+                }
+            }
+            data_type => {
+                if !is_valid(data_type, value) {
+                    validation.errors.push(format!("{} '{}' is not a valid {}", name, value, type_name(data_type)));
+                }
+            }
+        }
+    }
+
+    check_band(&mut validation, "FREQ", &qso.freq, &qso.band);
+    check_band(&mut validation, "FREQ_RX", &qso.freq_rx, &qso.band_rx);
+    validation
+}
+
+/// Flag a frequency (in MHz) that lies outside its band, or outside every band
+fn check_band(validation: &mut Validation, name: &str, freq: &str, band: &str) {
+    let Ok(mhz) = freq.trim().parse::<f64>() else {
+        return;
+    };
+    match qso::band_from_mhz(mhz) {
+        None => validation.warnings.push(format!("{} {} MHz is outside the ADIF band plan", name, freq)),
+        Some(plan_band) if !band.is_empty() && !plan_band.eq_ignore_ascii_case(band.trim()) => {
+            validation.warnings.push(format!("{} {} MHz is outside band {}", name, freq, band));
+        }
+        Some(_) => {}
+    }
+}
+
+/// Data type from a `<NAME:LEN:TYPE>` indicator
+fn declared_type(indicator: &str) -> Option<DataType> {
+    match indicator {
+        "D" => Some(DataType::Date),
+        "T" => Some(DataType::Time),
+        "N" => Some(DataType::Number),
+        "B" => Some(DataType::Boolean),
+        _ => None,
+    }
+}
+
+/// Name of a data type for messages
+fn type_name(data_type: DataType) -> &'static str {
+    match data_type {
+        DataType::Date => "date",
+        DataType::Time => "time",
+        DataType::GridSquare => "grid square",
+        DataType::Number => "number",
+        DataType::Integer => "integer",
+        DataType::Boolean => "boolean",
+        DataType::Band => "band",
+        DataType::Enumeration(_) => "enumeration value",
+    }
+}
+
+/// Check a value against a non-enumeration data type
+fn is_valid(data_type: DataType, value: &str) -> bool {
+    let value = value.trim();
+    match data_type {
+        DataType::Date => is_date(value),
+        DataType::Time => is_time(value),
+        DataType::GridSquare => is_grid_square(value),
+        DataType::Number => is_number(value),
+        DataType::Integer => !value.is_empty() && value.bytes().all(|b| b.is_ascii_digit()),
+        DataType::Boolean => value.eq_ignore_ascii_case("Y") || value.eq_ignore_ascii_case("N"),
+        DataType::Band | DataType::Enumeration(_) => true,
+    }
+}
+
+/// `YYYYMMDD`, from 1930 on
+fn is_date(value: &str) -> bool {
+    if value.len() != 8 || !value.bytes().all(|b| b.is_ascii_digit()) {
+        return false;
+    }
+    let year: u32 = value[..4].parse().unwrap_or(0);
+    let month: u32 = value[4..6].parse().unwrap_or(0);
+    let day: u32 = value[6..].parse().unwrap_or(0);
+    let leap_year = year.is_multiple_of(4) && (!year.is_multiple_of(100) || year.is_multiple_of(400));
+    let days = match month {
+        1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+        4 | 6 | 9 | 11 => 30,
+        2 if leap_year => 29,
+        2 => 28,
+        _ => return false,
+    };
+    year >= 1930 && (1..=days).contains(&day)
+}
+
+/// `HHMM` or `HHMMSS`
+fn is_time(value: &str) -> bool {
+    if !(value.len() == 4 || value.len() == 6) || !value.bytes().all(|b| b.is_ascii_digit()) {
+        return false;
+    }
+    let part = |range: std::ops::Range<usize>| value.get(range).and_then(|part| part.parse::<u32>().ok());
+    part(0..2).is_some_and(|hours| hours < 24)
+        && part(2..4).is_some_and(|minutes| minutes < 60)
+        && part(4..6).is_none_or(|seconds| seconds < 60)
+}
+
+/// Maidenhead locator with 2, 4, 6 or 8 characters, e.g. `JO`, `JO62`, `JO62qm`, `JO62qm15`
+fn is_grid_square(value: &str) -> bool {
+    let bytes = value.as_bytes();
+    matches!(bytes.len(), 2 | 4 | 6 | 8)
+        && bytes.iter().enumerate().all(|(index, b)| match index {
+            0 | 1 => (b'A'..=b'R').contains(&b.to_ascii_uppercase()),
+            4 | 5 => (b'A'..=b'X').contains(&b.to_ascii_uppercase()),
+            _ => b.is_ascii_digit(),
+        })
+}
+
+/// Decimal number with an optional sign, e.g. `-1.5` or `14.074`
+fn is_number(value: &str) -> bool {
+    let digits = value.strip_prefix('-').unwrap_or(value);
+    let mut parts = digits.splitn(2, '.');
+    let whole = parts.next().unwrap_or_default();
+    let fraction = parts.next().unwrap_or_default();
+    !(whole.is_empty() && fraction.is_empty())
+        && whole.bytes().all(|b| b.is_ascii_digit())
+        && fraction.bytes().all(|b| b.is_ascii_digit())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VALID: &str = "<CALL:6>DL1ABC<QSO_DATE:8>20240315<TIME_ON:4>1234<BAND:3>20m";
+
+    fn validate_adif(adif: &str) -> Validation {
+        let mut qsos = QSO::from_adif(adif.as_bytes(), encoding_rs::UTF_8);
+        assert_eq!(qsos.len(), 1);
+        validate(&qsos.remove(0).unwrap())
+    }
+
+    #[test]
+    fn request_examples_are_errors() {
+        let validation = validate_adif("<CALL:6>DL1ABC<QSO_DATE:10>2024-13-45<TIME_ON:4>1234<BAND:3>20m\
+            <GRIDSQUARE:5>JO62q<FREQ:3>abc<EOR>");
+        assert_eq!(validation.errors, [
+            "QSO_DATE '2024-13-45' is not a valid date",
+            "GRIDSQUARE 'JO62q' is not a valid grid square",
+            "FREQ 'abc' is not a valid number",
+        ]);
+        assert!(validation.warnings.is_empty());
+    }
+
+    #[test]
+    fn valid_qso_has_no_problems() {
+        let validation = validate_adif(&format!("{}<FREQ:6>14.074<MODE:3>FT8<GRIDSQUARE:6>JO62qm\
+            <QSL_RCVD:1>y<CQZ:2>14<SWL:1>N<EOR>", VALID));
+        assert!(validation.errors.is_empty(), "{:?}", validation.errors);
+        assert!(validation.warnings.is_empty(), "{:?}", validation.warnings);
+    }
+
+    #[test]
+    fn missing_required_fields_are_errors() {
+        let validation = validate_adif("<CALL:6>DL1ABC<FREQ:6>14.074<EOR>");
+        assert_eq!(validation.errors, ["QSO_DATE is missing", "TIME_ON is missing", "BAND is missing"]);
+    }
+
+    #[test]
+    fn enumerations_are_warnings_and_types_are_errors() {
+        let validation = validate_adif(&format!("{}<MODE:3>FOO<QSL_RCVD:1>X<PROP_MODE:3>F2X\
+            <CQZ:2>1a<SWL:3>YES<EPC:3:N>abc<MY_DATE:8:D>20241232<EOR>", VALID));
+        assert_eq!(validation.warnings, [
+            "MODE 'FOO' is not an ADIF value",
+            "QSL_RCVD 'X' is not an ADIF value",
+            "PROP_MODE 'F2X' is not an ADIF value",
+        ]);
+        assert_eq!(validation.errors, [
+            "CQZ '1a' is not a valid integer",
+            "SWL 'YES' is not a valid boolean",
+            "EPC 'abc' is not a valid number",
+            "MY_DATE '20241232' is not a valid date",
+        ]);
+    }
+
+    #[test]
+    fn frequencies_outside_their_band_are_warnings() {
+        let validation = validate_adif("<CALL:6>DL1ABC<QSO_DATE:8>20240315<TIME_ON:4>1234<BAND:3>40m\
+            <FREQ:6>14.074<FREQ_RX:4>15.0<EOR>");
+        assert!(validation.errors.is_empty());
+        assert_eq!(validation.warnings, [
+            "FREQ 14.074 MHz is outside band 40m",
+            "FREQ_RX 15.0 MHz is outside the ADIF band plan",
+        ]);
+
+        let validation = validate_adif(&format!("{}<FREQ:9>14.074000<BAND_RX:2>5m<FREQ_RX:9>54.000001<EOR>", VALID));
+        assert!(validation.warnings.is_empty(), "{:?}", validation.warnings);
+
+        let validation = validate_adif(&format!("{}<FREQ:4>54.0<EOR>", VALID.replace("<BAND:3>20m", "<BAND:2>6M")));
+        assert!(validation.warnings.is_empty(), "{:?}", validation.warnings);
+
+        let validation = validate_adif(&format!("{}<BAND_RX:3>21m<EOR>", VALID));
+        assert_eq!(validation.warnings, ["BAND_RX '21m' is not an ADIF band"]);
+    }
+
+    #[test]
+    fn dates() {
+        for date in ["20240315", "20240229", "20000229", "19300101", "20241231"] {
+            assert!(is_date(date), "{}", date);
+        }
+        for date in ["20230229", "21000229", "19291231", "20241301", "20240001", "20240431",
+            "20240100", "2024031", "2024-03-15", ""] {
+            assert!(!is_date(date), "{}", date);
+        }
+    }
+
+    #[test]
+    fn times() {
+        for time in ["0000", "2359", "235959", "120000"] {
+            assert!(is_time(time), "{}", time);
+        }
+        for time in ["2400", "1260", "123460", "123", "12345", "12:34", ""] {
+            assert!(!is_time(time), "{}", time);
+        }
+    }
+
+    #[test]
+    fn grid_squares() {
+        for grid in ["JO", "JO62", "jo62qm", "JO62QM15", "RR99xx99", "AA00aa00"] {
+            assert!(is_grid_square(grid), "{}", grid);
+        }
+        for grid in ["J", "JO6", "JO62q", "SA62", "JO62ya", "JO62qmA5", "JO62qm1", ""] {
+            assert!(!is_grid_square(grid), "{}", grid);
+        }
+    }
+
+    #[test]
+    fn numbers() {
+        for number in ["14", "14.074", "-1.5", ".5", "5.", "0"] {
+            assert!(is_number(number), "{}", number);
+        }
+        for number in ["", "-", ".", "1.2.3", "abc", "1e3", "+1", "1,5", " 1"] {
+            assert!(!is_number(number), "{}", number);
+        }
+    }
+}
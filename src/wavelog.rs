@@ -1,8 +1,9 @@
+use crate::cat::RadioStatus;
 use crate::qso::QSO;
 use crate::settings::Settings;
 
 use serde::Serialize;
-use reqwest::{Client, header};
+use reqwest::{Client, StatusCode, header};
 use std::time::Duration;
 use std::error::Error;
 
@@ -18,6 +19,16 @@ struct WavelogPayload {
 
 }
 
+#[derive(Serialize)]
+struct RadioPayload {
+    key: String,
+    radio: String,
+    frequency: u64,
+    mode: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    power: Option<f64>,
+}
+
 pub async fn send(qso: &QSO,  settings: &Settings) -> Result<String, Box<dyn Error>> {
     // Create a client with the appropriate settings
     let client = Client::builder()
@@ -54,11 +65,106 @@ pub async fn send(qso: &QSO,  settings: &Settings) -> Result<String, Box<dyn Err
     // Get the response body
     let res_string = response.text().await?;
     // Check if request was successful
-    if !status_code.is_success() {
-        if res_string.contains("html>") {
-            return Err("Wrong URL".into());
+    Ok(check_response(status_code, res_string)?)
+}
+
+pub async fn send_radio(status: &RadioStatus, radio: &str, settings: &Settings) -> Result<String, Box<dyn Error>> {
+    // Create a client with the appropriate settings
+    let client = Client::builder()
+        .timeout(Duration::from_secs(5))
+        .danger_accept_invalid_certs(true)
+        .build()?;
+    // Prepare the payload
+    let payload = RadioPayload {
+        key: settings.wavelog.key.trim().to_string(),
+        radio: radio.to_string(),
+        frequency: status.frequency,
+        mode: status.mode.clone(),
+        power: status.power,
+    };
+    // Prepare the URL
+    let url = format!("{}/api/radio", settings.wavelog.url);
+    let version = option_env!("CARGO_PKG_VERSION").unwrap_or("1.0");
+
+    // Send the request
+    let response = client
+        .post(&url)
+        .header(header::USER_AGENT, format!("RustClient_v{}", version))
+        .json(&payload)
+        .send()
+        .await?;
+
+    let status_code = response.status();
+    let res_string = response.text().await?;
+    Ok(check_response(status_code, res_string)?)
+}
+
+/// Check the status and body of a Wavelog API response
+fn check_response(status_code: StatusCode, body: String) -> Result<String, WavelogError> {
+    if status_code.is_success() {
+        return Ok(body);
+    }
+    if body.contains("html>") {
+        // An HTML page comes from a web server or proxy in front of Wavelog, not from its API
+        return Err(WavelogError::WrongUrl);
+    }
+    if status_code.is_client_error() && serde_json::from_str::<serde_json::Value>(&body).is_ok() {
+        return Err(WavelogError::Rejected(body));
+    }
+    Err(WavelogError::Unavailable(status_code, body))
+}
+
+/// Wavelog API error types
+#[derive(Debug)]
+pub enum WavelogError {
+    /// Wavelog refused the request, e.g. because of a wrong key or a duplicate QSO
+    Rejected(String),
+    /// The server answered with an HTML page instead of the API
+    WrongUrl,
+    /// The server answered with an error that did not come from the Wavelog API
+    Unavailable(StatusCode, String),
+}
+
+impl WavelogError {
+    /// Whether a failed request was answered by Wavelog itself
+    /// Sending again will not change the result of such a request
+    pub fn is_rejection(error: &(dyn Error + 'static)) -> bool {
+        matches!(error.downcast_ref::<WavelogError>(), Some(WavelogError::Rejected(_)))
+    }
+}
+
+impl std::fmt::Display for WavelogError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WavelogError::Rejected(body) => write!(f, "{}", body),
+            WavelogError::WrongUrl => write!(f, "Wrong URL"),
+            WavelogError::Unavailable(status, body) if body.trim().is_empty() => write!(f, "Wavelog unavailable ({})", status),
+            WavelogError::Unavailable(_, body) => write!(f, "{}", body),
         }
-        return Err(res_string.into());
     }
-    Ok(res_string)
-}
\ No newline at end of file
+}
+
+impl Error for WavelogError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_api_errors_are_rejections() {
+        let api = r#"{"status":"failed","reason":"wrong JSON"}"#.to_string();
+        let html = "<html><body>Bad Gateway</body></html>".to_string();
+
+        assert_eq!(check_response(StatusCode::CREATED, api.clone()).unwrap(), api);
+        assert!(matches!(check_response(StatusCode::UNAUTHORIZED, api.clone()), Err(WavelogError::Rejected(_))));
+        assert!(matches!(check_response(StatusCode::NOT_FOUND, html.clone()), Err(WavelogError::WrongUrl)));
+        assert!(matches!(check_response(StatusCode::BAD_GATEWAY, html), Err(WavelogError::WrongUrl)));
+        assert!(matches!(check_response(StatusCode::INTERNAL_SERVER_ERROR, api), Err(WavelogError::Unavailable(..))));
+        assert!(matches!(check_response(StatusCode::SERVICE_UNAVAILABLE, String::new()), Err(WavelogError::Unavailable(..))));
+
+        let rejected: Box<dyn Error> = Box::new(WavelogError::Rejected("duplicate".to_string()));
+        let unavailable: Box<dyn Error> = Box::new(WavelogError::WrongUrl);
+        assert!(WavelogError::is_rejection(rejected.as_ref()));
+        assert!(!WavelogError::is_rejection(unavailable.as_ref()));
+    }
+}
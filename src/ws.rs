@@ -0,0 +1,195 @@
+use crate::cat::RadioStatus;
+use crate::qso::QSO;
+
+use iced::futures::{SinkExt, Stream, StreamExt};
+use serde::Serialize;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::broadcast;
+use tokio_tungstenite::tungstenite::Message;
+
+/// Number of events buffered for a slow client before it starts missing some
+const CLIENT_BACKLOG: usize = 64;
+
+/// Local WebSocket server compatible with WaveLogGate's live event feed
+/// Wavelog's live-logging page connects to `ws://127.0.0.1:54322`
+#[derive(Debug, Clone)]
+pub struct WsServer {
+    port: u16,
+}
+
+/// Events produced by a running WebSocket server
+#[derive(Debug, Clone)]
+pub enum WsEvent {
+    /// The server accepts events to publish through this handle
+    Ready(WsBroadcaster),
+    /// The server could not be started
+    Error(String),
+}
+
+/// Publishes JSON events to every connected WebSocket client
+#[derive(Debug, Clone)]
+pub struct WsBroadcaster {
+    sender: broadcast::Sender<String>,
+    /// Latest radio status, sent to clients as soon as they connect
+    last_radio: Arc<Mutex<Option<String>>>,
+}
+
+/// JSON event sent to WebSocket clients
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum WsMessage<'a> {
+    RadioStatus {
+        radio: &'a str,
+        frequency: u64,
+        mode: &'a str,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        power: Option<f64>,
+        timestamp: u64,
+    },
+    QsoLogged {
+        call: &'a str,
+        gridsquare: &'a str,
+        band: &'a str,
+        freq: &'a str,
+        mode: &'a str,
+        submode: &'a str,
+        rst_sent: &'a str,
+        rst_rcvd: &'a str,
+        qso_date: &'a str,
+        time_on: &'a str,
+        station_callsign: &'a str,
+        /// Upload status, `OK` or the error returned by Wavelog
+        status: &'a str,
+        timestamp: u64,
+    },
+}
+
+impl WsBroadcaster {
+    /// Publish a radio status change
+    pub fn radio_status(&self, status: &RadioStatus, radio: &str) {
+        let message = WsMessage::RadioStatus {
+            radio,
+            frequency: status.frequency,
+            mode: &status.mode,
+            power: status.power,
+            timestamp: timestamp(),
+        };
+        if let Some(json) = self.publish(&message) {
+            *self.last_radio.lock().unwrap_or_else(|e| e.into_inner()) = Some(json);
+        }
+    }
+
+    /// Publish a processed QSO together with its upload status
+    pub fn qso_logged(&self, qso: &QSO, status: &str) {
+        self.publish(&WsMessage::QsoLogged {
+            call: &qso.call,
+            gridsquare: &qso.gridsquare,
+            band: &qso.band,
+            freq: &qso.freq,
+            mode: &qso.mode,
+            submode: &qso.submode,
+            rst_sent: &qso.rst_sent,
+            rst_rcvd: &qso.rst_rcvd,
+            qso_date: &qso.qso_date,
+            time_on: &qso.time_on,
+            station_callsign: &qso.station_callsign,
+            status,
+            timestamp: timestamp(),
+        });
+    }
+
+    /// Serialize an event and send it to all clients, returning the JSON text
+    fn publish(&self, message: &WsMessage) -> Option<String> {
+        let json = serde_json::to_string(message).ok()?;
+        // Sending only fails when no client is connected
+        let _ = self.sender.send(json.clone());
+        Some(json)
+    }
+}
+
+impl WsServer {
+    /// Create a new WebSocket server instance, listening on localhost only
+    pub fn new(port: u16) -> Self {
+        Self { port }
+    }
+
+    /// Accept WebSocket clients for the lifetime of the stream
+    pub fn run(self) -> impl Stream<Item = WsEvent> {
+        iced::stream::channel(100, move |mut output| async move {
+            let listener = match TcpListener::bind(("127.0.0.1", self.port)).await {
+                Ok(listener) => listener,
+                Err(e) => {
+                    let error = format!("Failed to start WebSocket server on port {}: {}", self.port, e);
+                    let _ = output.send(WsEvent::Error(error)).await;
+                    return;
+                }
+            };
+
+            let (sender, _) = broadcast::channel(CLIENT_BACKLOG);
+            let broadcaster = WsBroadcaster { sender, last_radio: Arc::new(Mutex::new(None)) };
+            if output.send(WsEvent::Ready(broadcaster.clone())).await.is_err() {
+                return;
+            }
+
+            loop {
+                match listener.accept().await {
+                    Ok((stream, _src)) => {
+                        tokio::spawn(Self::handle_connection(stream, broadcaster.clone()));
+                    }
+                    Err(e) => {
+                        eprintln!("WebSocket accept error: {}", e);
+                        tokio::time::sleep(Duration::from_millis(100)).await;
+                    }
+                }
+            }
+        })
+    }
+
+    /// Forward published events to one client until it disconnects
+    /// Messages sent by the client are read only to answer pings and detect closing
+    async fn handle_connection(stream: TcpStream, broadcaster: WsBroadcaster) {
+        let mut events = broadcaster.sender.subscribe();
+        let mut socket = match tokio_tungstenite::accept_async(stream).await {
+            Ok(socket) => socket,
+            Err(e) => {
+                eprintln!("WebSocket handshake failed: {}", e);
+                return;
+            }
+        };
+
+        let last_radio = broadcaster.last_radio.lock().unwrap_or_else(|e| e.into_inner()).clone();
+        if let Some(json) = last_radio
+            && socket.send(Message::Text(json)).await.is_err() {
+            return;
+        }
+
+        loop {
+            tokio::select! {
+                event = events.recv() => match event {
+                    Ok(json) => {
+                        if socket.send(Message::Text(json)).await.is_err() {
+                            return;
+                        }
+                    }
+                    // The client fell behind, skip the events it missed
+                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => return,
+                },
+                message = socket.next() => match message {
+                    Some(Ok(Message::Close(_))) | Some(Err(_)) | None => return,
+                    Some(Ok(_)) => continue,
+                },
+            }
+        }
+    }
+}
+
+/// Milliseconds since the Unix epoch
+fn timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis() as u64)
+        .unwrap_or_default()
+}
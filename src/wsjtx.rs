@@ -0,0 +1,453 @@
+use crate::cat::RadioStatus;
+use crate::qso::{self, QSO};
+
+/// Magic number at the start of every WSJT-X UDP message
+const MAGIC: u32 = 0xADBCCBDA;
+
+/// Decoded WSJT-X (and JTDX / MSHV) UDP message
+#[allow(dead_code)]
+#[derive(Debug, Clone)]
+pub enum WsjtxMessage {
+    /// Type 0: sent periodically by the logging program
+    Heartbeat(Heartbeat),
+    /// Type 1: current radio and decoder state
+    Status(Status),
+    /// Type 5: a QSO has been logged
+    QsoLogged(QsoLogged),
+    /// Type 12: a QSO has been logged, as an ADIF record
+    LoggedAdif(String),
+    /// Any other message type, which the gate does not use
+    Other(u32),
+}
+
+/// WSJT-X Heartbeat message
+/// Fields are decoded in protocol order; not all of them are used by the gate
+#[allow(dead_code)]
+#[derive(Debug, Clone)]
+pub struct Heartbeat {
+    pub id: String,
+    pub max_schema: u32,
+    pub version: String,
+    pub revision: String,
+}
+
+/// WSJT-X Status message
+/// Fields are decoded in protocol order; not all of them are used by the gate
+#[allow(dead_code)]
+#[derive(Debug, Clone, Default)]
+pub struct Status {
+    pub id: String,
+    pub dial_frequency: u64,
+    pub mode: String,
+    pub dx_call: String,
+    pub report: String,
+    pub tx_mode: String,
+    pub tx_enabled: bool,
+    pub transmitting: bool,
+    pub decoding: bool,
+    pub rx_df: u32,
+    pub tx_df: u32,
+    pub de_call: String,
+    pub de_grid: String,
+    pub dx_grid: String,
+    pub tx_watchdog: bool,
+    pub sub_mode: String,
+    pub fast_mode: bool,
+}
+
+/// WSJT-X QSO Logged message
+/// Fields are decoded in protocol order; not all of them are used by the gate
+#[allow(dead_code)]
+#[derive(Debug, Clone, Default)]
+pub struct QsoLogged {
+    pub id: String,
+    pub date_time_off: DateTime,
+    pub dx_call: String,
+    pub dx_grid: String,
+    pub tx_frequency: u64,
+    pub mode: String,
+    pub report_sent: String,
+    pub report_received: String,
+    pub tx_power: String,
+    pub comments: String,
+    pub name: String,
+    pub date_time_on: DateTime,
+    pub operator_call: String,
+    pub my_call: String,
+    pub my_grid: String,
+    pub exchange_sent: String,
+    pub exchange_received: String,
+    pub propagation_mode: String,
+}
+
+/// UTC date and time decoded from a QDateTime
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DateTime {
+    pub year: i64,
+    pub month: u32,
+    pub day: u32,
+    pub msecs: u32,
+}
+
+impl DateTime {
+    /// Format as an ADIF date (YYYYMMDD)
+    pub fn adif_date(&self) -> String {
+        format!("{:04}{:02}{:02}", self.year, self.month, self.day)
+    }
+
+    /// Format as an ADIF time (HHMMSS)
+    pub fn adif_time(&self) -> String {
+        let secs = self.msecs / 1000;
+        format!("{:02}{:02}{:02}", secs / 3600, (secs / 60) % 60, secs % 60)
+    }
+}
+
+impl Status {
+    /// Dial frequency and mode as a radio status, for operators without a CAT link
+    pub fn to_radio_status(&self) -> RadioStatus {
+        RadioStatus { frequency: self.dial_frequency, mode: self.mode.clone(), power: None }
+    }
+}
+
+impl QsoLogged {
+    /// Convert the logged QSO into a QSO record for upload
+    pub fn to_qso(&self) -> QSO {
+        let mut qso = QSO {
+            call: self.dx_call.clone(),
+            gridsquare: self.dx_grid.clone(),
+            mode: self.mode.clone(),
+            rst_sent: self.report_sent.clone(),
+            rst_rcvd: self.report_received.clone(),
+            qso_date: self.date_time_on.adif_date(),
+            time_on: self.date_time_on.adif_time(),
+            qso_date_off: self.date_time_off.adif_date(),
+            time_off: self.date_time_off.adif_time(),
+            band: qso::band_from_hz(self.tx_frequency).unwrap_or_default().to_string(),
+            freq: format!("{:.6}", self.tx_frequency as f64 / 1_000_000.0),
+            operator: self.operator_call.clone(),
+            comment: self.comments.clone(),
+            my_gridsquare: self.my_grid.clone(),
+            station_callsign: self.my_call.clone(),
+            srx_string: self.exchange_received.clone(),
+            ..Default::default()
+        };
+        // Entered as free text, often with a unit, e.g. `100W`
+        qso.push_field("TX_PWR", self.tx_power.trim().trim_end_matches(['W', 'w']).trim());
+        qso.push_field("NAME", self.name.clone());
+        qso.push_field("STX_STRING", self.exchange_sent.clone());
+        qso.push_field("PROP_MODE", self.propagation_mode.clone());
+        qso
+    }
+}
+
+/// Check whether a datagram looks like a WSJT-X binary message
+pub fn is_wsjtx(data: &[u8]) -> bool {
+    data.len() >= 4 && u32::from_be_bytes([data[0], data[1], data[2], data[3]]) == MAGIC
+}
+
+/// Decode a WSJT-X binary UDP message
+pub fn decode(data: &[u8]) -> Result<WsjtxMessage, WsjtxError> {
+    let mut reader = Reader::new(data);
+
+    let magic = reader.u32()?;
+    if magic != MAGIC {
+        return Err(WsjtxError::BadMagic(magic));
+    }
+    let _schema = reader.u32()?;
+    let message_type = reader.u32()?;
+    let id = reader.utf8()?;
+
+    let message = match message_type {
+        0 => WsjtxMessage::Heartbeat(Heartbeat {
+            id,
+            max_schema: reader.u32()?,
+            version: reader.utf8()?,
+            revision: reader.utf8()?,
+        }),
+        1 => WsjtxMessage::Status(Status {
+            id,
+            dial_frequency: reader.u64()?,
+            mode: reader.utf8()?,
+            dx_call: reader.utf8()?,
+            report: reader.utf8()?,
+            tx_mode: reader.utf8()?,
+            tx_enabled: reader.bool()?,
+            transmitting: reader.bool()?,
+            decoding: reader.bool()?,
+            rx_df: reader.u32()?,
+            tx_df: reader.u32()?,
+            de_call: reader.utf8()?,
+            de_grid: reader.utf8()?,
+            dx_grid: reader.utf8()?,
+            // Fields below were added in later schema revisions
+            tx_watchdog: reader.bool().unwrap_or_default(),
+            sub_mode: reader.utf8().unwrap_or_default(),
+            fast_mode: reader.bool().unwrap_or_default(),
+        }),
+        5 => WsjtxMessage::QsoLogged(QsoLogged {
+            id,
+            date_time_off: reader.date_time()?,
+            dx_call: reader.utf8()?,
+            dx_grid: reader.utf8()?,
+            tx_frequency: reader.u64()?,
+            mode: reader.utf8()?,
+            report_sent: reader.utf8()?,
+            report_received: reader.utf8()?,
+            tx_power: reader.utf8()?,
+            comments: reader.utf8()?,
+            name: reader.utf8()?,
+            date_time_on: reader.date_time()?,
+            // Fields below were added in later schema revisions
+            operator_call: reader.utf8().unwrap_or_default(),
+            my_call: reader.utf8().unwrap_or_default(),
+            my_grid: reader.utf8().unwrap_or_default(),
+            exchange_sent: reader.utf8().unwrap_or_default(),
+            exchange_received: reader.utf8().unwrap_or_default(),
+            propagation_mode: reader.utf8().unwrap_or_default(),
+        }),
+        12 => WsjtxMessage::LoggedAdif(reader.utf8()?),
+        other => WsjtxMessage::Other(other),
+    };
+    Ok(message)
+}
+
+/// Big-endian QDataStream reader
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, pos: 0 }
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], WsjtxError> {
+        let end = self.pos.checked_add(len)
+            .filter(|end| *end <= self.data.len())
+            .ok_or(WsjtxError::Truncated)?;
+        let bytes = &self.data[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8, WsjtxError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn bool(&mut self) -> Result<bool, WsjtxError> {
+        Ok(self.u8()? != 0)
+    }
+
+    fn u32(&mut self) -> Result<u32, WsjtxError> {
+        let bytes = self.take(4)?;
+        Ok(u32::from_be_bytes(bytes.try_into().unwrap()))
+    }
+
+    fn i32(&mut self) -> Result<i32, WsjtxError> {
+        Ok(self.u32()? as i32)
+    }
+
+    fn u64(&mut self) -> Result<u64, WsjtxError> {
+        let bytes = self.take(8)?;
+        Ok(u64::from_be_bytes(bytes.try_into().unwrap()))
+    }
+
+    fn i64(&mut self) -> Result<i64, WsjtxError> {
+        Ok(self.u64()? as i64)
+    }
+
+    /// Read a utf8 string serialized as a QByteArray (0xffffffff means null)
+    fn utf8(&mut self) -> Result<String, WsjtxError> {
+        let len = self.u32()?;
+        if len == u32::MAX {
+            return Ok(String::new());
+        }
+        let bytes = self.take(len as usize)?;
+        Ok(String::from_utf8_lossy(bytes).into_owned())
+    }
+
+    /// Read a QDateTime and convert it to a calendar date and time
+    fn date_time(&mut self) -> Result<DateTime, WsjtxError> {
+        let julian_day = self.i64()?;
+        let msecs = self.u32()?;
+        let timespec = self.u8()?;
+        if timespec == 2 {
+            // Offset from UTC in seconds, WSJT-X always sends UTC
+            let _offset = self.i32()?;
+        }
+        let (year, month, day) = julian_day_to_date(julian_day);
+        Ok(DateTime { year, month, day, msecs })
+    }
+}
+
+/// Convert a Julian day number to a Gregorian calendar date
+fn julian_day_to_date(julian_day: i64) -> (i64, u32, u32) {
+    let a = julian_day + 32044;
+    let b = (4 * a + 3) / 146097;
+    let c = a - 146097 * b / 4;
+    let d = (4 * c + 3) / 1461;
+    let e = c - 1461 * d / 4;
+    let m = (5 * e + 2) / 153;
+    let day = e - (153 * m + 2) / 5 + 1;
+    let month = m + 3 - 12 * (m / 10);
+    let year = 100 * b + d - 4800 + m / 10;
+    (year, month as u32, day as u32)
+}
+
+/// WSJT-X decoder error types
+#[derive(Debug)]
+pub enum WsjtxError {
+    BadMagic(u32),
+    Truncated,
+}
+
+impl std::fmt::Display for WsjtxError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WsjtxError::BadMagic(magic) => {
+                write!(f, "Not a WSJT-X message (magic {:#010x})", magic)
+            }
+            WsjtxError::Truncated => {
+                write!(f, "WSJT-X message is truncated")
+            }
+        }
+    }
+}
+
+impl std::error::Error for WsjtxError {}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    /// Heartbeat of WSJT-X 2.6.1, schema 3, with a null revision
+    const HEARTBEAT: &[u8] = b"\
+        \xad\xbc\xcb\xda\x00\x00\x00\x03\x00\x00\x00\x00\x00\x00\x00\x06\
+        \x57\x53\x4a\x54\x2d\x58\x00\x00\x00\x03\x00\x00\x00\x05\x32\x2e\
+        \x36\x2e\x31\xff\xff\xff\xff\
+        ";
+
+    /// Status while receiving FT8 on 14.074 MHz, with a null sub-mode and trailing schema 3 fields
+    const STATUS: &[u8] = b"\
+        \xad\xbc\xcb\xda\x00\x00\x00\x03\x00\x00\x00\x01\x00\x00\x00\x06\
+        \x57\x53\x4a\x54\x2d\x58\x00\x00\x00\x00\x00\xd6\xc0\x90\x00\x00\
+        \x00\x03\x46\x54\x38\x00\x00\x00\x06\x44\x4c\x31\x41\x42\x43\x00\
+        \x00\x00\x03\x2d\x31\x30\x00\x00\x00\x03\x46\x54\x38\x01\x00\x01\
+        \x00\x00\x05\xdc\x00\x00\x04\xb0\x00\x00\x00\x05\x4b\x31\x41\x42\
+        \x43\x00\x00\x00\x04\x46\x4e\x34\x32\x00\x00\x00\x04\x4a\x4f\x36\
+        \x32\x00\xff\xff\xff\xff\x00\x00\xff\xff\xff\xff\x00\x00\x00\x0f\
+        \x00\x00\x00\x07\x44\x65\x66\x61\x75\x6c\x74\x00\x00\x00\x11\x44\
+        \x4c\x31\x41\x42\x43\x20\x4b\x31\x41\x42\x43\x20\x52\x2d\x31\x32\
+        ";
+
+    /// QSO Logged for DL1ABC on 2024-03-15 12:34:00 - 12:35:30 UTC, with null name and operator
+    pub const QSO_LOGGED: &[u8] = b"\
+        \xad\xbc\xcb\xda\x00\x00\x00\x03\x00\x00\x00\x05\x00\x00\x00\x06\
+        \x57\x53\x4a\x54\x2d\x58\x00\x00\x00\x00\x00\x25\x8a\xe1\x02\xb3\
+        \xae\x50\x01\x00\x00\x00\x06\x44\x4c\x31\x41\x42\x43\x00\x00\x00\
+        \x04\x4a\x4f\x36\x32\x00\x00\x00\x00\x00\xd6\xc0\x90\x00\x00\x00\
+        \x03\x46\x54\x38\x00\x00\x00\x03\x2d\x31\x30\x00\x00\x00\x03\x2d\
+        \x31\x32\x00\x00\x00\x04\x31\x30\x30\x57\x00\x00\x00\x03\x74\x6e\
+        \x78\xff\xff\xff\xff\x00\x00\x00\x00\x00\x25\x8a\xe1\x02\xb2\x4e\
+        \xc0\x01\xff\xff\xff\xff\x00\x00\x00\x05\x4b\x31\x41\x42\x43\x00\
+        \x00\x00\x04\x46\x4e\x34\x32\xff\xff\xff\xff\xff\xff\xff\xff\x00\
+        \x00\x00\x00\
+        ";
+
+    /// Header of a Logged ADIF message from WSJT-X, followed by the ADIF length and text
+    pub const LOGGED_ADIF_HEAD: &[u8] = b"\xad\xbc\xcb\xda\x00\x00\x00\x03\x00\x00\x00\x0c\x00\x00\x00\x06\x57\x53\x4a\x54\x2d\x58";
+
+    pub const LOGGED_ADIF: &str = "\n<adif_ver:5>3.1.0\n<programid:6>WSJT-X\n<EOH>\n<call:6>DL1ABC <gridsquare:4>JO62 \
+        <mode:3>FT8 <rst_sent:3>-10 <rst_rcvd:3>-12 <qso_date:8>20240315 <time_on:6>123400 \
+        <qso_date_off:8>20240315 <time_off:6>123530 <band:3>20m <freq:9>14.074000 \
+        <station_callsign:5>K1ABC <my_gridsquare:4>FN42 <tx_pwr:3>100 <comment:3>tnx <EOR>";
+
+    /// Logged ADIF message carrying the given ADIF text
+    pub fn logged_adif(adif: &str) -> Vec<u8> {
+        let mut datagram = LOGGED_ADIF_HEAD.to_vec();
+        datagram.extend_from_slice(&(adif.len() as u32).to_be_bytes());
+        datagram.extend_from_slice(adif.as_bytes());
+        datagram
+    }
+
+    #[test]
+    fn decodes_heartbeat() {
+        assert!(is_wsjtx(HEARTBEAT));
+        let Ok(WsjtxMessage::Heartbeat(heartbeat)) = decode(HEARTBEAT) else {
+            panic!("not a heartbeat");
+        };
+        assert_eq!(heartbeat.id, "WSJT-X");
+        assert_eq!(heartbeat.max_schema, 3);
+        assert_eq!(heartbeat.version, "2.6.1");
+        assert_eq!(heartbeat.revision, "");
+    }
+
+    #[test]
+    fn decodes_status() {
+        let Ok(WsjtxMessage::Status(status)) = decode(STATUS) else {
+            panic!("not a status");
+        };
+        assert_eq!(status.dial_frequency, 14_074_000);
+        assert_eq!(status.mode, "FT8");
+        assert_eq!(status.dx_call, "DL1ABC");
+        assert!(status.tx_enabled && !status.transmitting && status.decoding);
+        assert_eq!((status.rx_df, status.tx_df), (1500, 1200));
+        assert_eq!(status.de_grid, "FN42");
+        assert_eq!(status.sub_mode, "");
+        assert_eq!(status.to_radio_status().frequency, 14_074_000);
+    }
+
+    #[test]
+    fn decodes_qso_logged() {
+        let Ok(WsjtxMessage::QsoLogged(logged)) = decode(QSO_LOGGED) else {
+            panic!("not a logged QSO");
+        };
+        assert_eq!(logged.name, "");
+        assert_eq!(logged.operator_call, "");
+        assert_eq!(logged.my_call, "K1ABC");
+
+        let qso = logged.to_qso();
+        assert_eq!(qso.call, "DL1ABC");
+        assert_eq!((qso.qso_date.as_str(), qso.time_on.as_str()), ("20240315", "123400"));
+        assert_eq!((qso.qso_date_off.as_str(), qso.time_off.as_str()), ("20240315", "123530"));
+        assert_eq!((qso.band.as_str(), qso.freq.as_str()), ("20m", "14.074000"));
+        assert!(qso.power.is_empty());
+        let adif = qso.to_adif();
+        assert!(adif.contains("<TX_PWR:3>100"));
+        assert!(!adif.contains("<POWER:"));
+        assert!(!adif.contains("<NAME:"));
+    }
+
+    #[test]
+    fn decodes_logged_adif() {
+        let Ok(WsjtxMessage::LoggedAdif(adif)) = decode(&logged_adif(LOGGED_ADIF)) else {
+            panic!("not a logged ADIF");
+        };
+        assert_eq!(adif, LOGGED_ADIF);
+
+        // Both reports of the same QSO upload the same power
+        let qso = QSO::from_adif(adif.as_bytes(), encoding_rs::UTF_8).remove(0).unwrap();
+        let Ok(WsjtxMessage::QsoLogged(logged)) = decode(QSO_LOGGED) else {
+            panic!("not a logged QSO");
+        };
+        assert!(qso.to_adif().contains("<TX_PWR:3>100"));
+        assert!(logged.to_qso().to_adif().contains("<TX_PWR:3>100"));
+    }
+
+    #[test]
+    fn rejects_truncated_and_foreign_datagrams() {
+        assert!(matches!(decode(&QSO_LOGGED[..40]), Err(WsjtxError::Truncated)));
+        assert!(matches!(decode(b"<call:4>DL1A<eor>"), Err(WsjtxError::BadMagic(_))));
+        assert!(!is_wsjtx(b"<call:4>DL1A<eor>"));
+    }
+
+    #[test]
+    fn converts_julian_days() {
+        assert_eq!(julian_day_to_date(2_451_545), (2000, 1, 1));
+        assert_eq!(julian_day_to_date(2_440_588), (1970, 1, 1));
+        assert_eq!(julian_day_to_date(2_460_385), (2024, 3, 15));
+        assert_eq!(julian_day_to_date(2_460_370), (2024, 2, 29));
+        assert_eq!(julian_day_to_date(2_299_161), (1582, 10, 15));
+    }
+}